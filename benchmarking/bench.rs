@@ -1,11 +1,39 @@
+use std::fmt;
+
 use bencher::{benchmark_group, benchmark_main, Bencher};
-use ulid;
 
 fn benchmark_serialized(b: &mut Bencher) {
     let mut gen = ulid::UlidGenerator::new();
     b.iter(move || gen.ulid().to_string() )
 }
 
+/// Formats the way that `Display` did before it wrote the bytes
+/// directly, for comparison
+struct ViaWriteMacro(ulid::Ulid);
+
+impl fmt::Display for ViaWriteMacro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buf = <[u8; ulid::ULID_LEN]>::from(self.0);
+        let ascii_ulid = std::str::from_utf8(&buf).unwrap();
+        write!(f, "{}", ascii_ulid)
+    }
+}
+
+fn benchmark_serialized_via_write_macro(b: &mut Bencher) {
+    let mut gen = ulid::UlidGenerator::new();
+    b.iter(move || ViaWriteMacro(gen.ulid()).to_string() )
+}
+
+fn benchmark_write_canonical(b: &mut Bencher) {
+    let mut gen = ulid::UlidGenerator::new();
+    let mut out = String::with_capacity(ulid::ULID_LEN);
+    b.iter(move || {
+        out.clear();
+        gen.ulid().write_canonical(&mut out).unwrap();
+        out.len()
+    })
+}
+
 fn benchmark_generation(b: &mut Bencher) {
     let mut gen = ulid::UlidGenerator::new();
     b.iter(move || gen.ulid() )
@@ -14,7 +42,9 @@ fn benchmark_generation(b: &mut Bencher) {
 benchmark_group!(
     ulid_lite_perf,
     benchmark_serialized,
+    benchmark_serialized_via_write_macro,
+    benchmark_write_canonical,
     benchmark_generation
 );
 
-benchmark_main!(ulid_lite_perf);
\ No newline at end of file
+benchmark_main!(ulid_lite_perf);
@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ulid::{Ulid, UlidGenerator};
+
+fn generation(c: &mut Criterion) {
+    let mut gen = UlidGenerator::new();
+    c.bench_function("generation", |b| b.iter(|| gen.ulid()));
+}
+
+fn monotonic_generation(c: &mut Criterion) {
+    let mut gen = UlidGenerator::new();
+    c.bench_function("monotonic_generation", |b| b.iter(|| gen.next_monotonic()));
+}
+
+fn encoding(c: &mut Criterion) {
+    let id = Ulid::new();
+    c.bench_function("encoding", |b| b.iter(|| black_box(id).to_string()));
+}
+
+fn decoding(c: &mut Criterion) {
+    let text = Ulid::new().to_string();
+    c.bench_function("decoding", |b| b.iter(|| black_box(&text).parse::<Ulid>()));
+}
+
+fn batch_decoding(c: &mut Criterion) {
+    let texts: Vec<String> = UlidGenerator::new().take(1000).map(|id| id.to_string()).collect();
+    let mut ids = vec![Ulid::new_nil(); texts.len()];
+
+    let mut group = c.benchmark_group("batch_decoding");
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            for (text, id) in texts.iter().zip(&mut ids) {
+                *id = black_box(text).parse().unwrap();
+            }
+        })
+    });
+    group.bench_function("in_place", |b| {
+        b.iter(|| {
+            for (text, id) in texts.iter().zip(&mut ids) {
+                Ulid::from_str_radix32_into(black_box(text), id).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(ulid_lite_perf, generation, monotonic_generation, encoding, decoding, batch_decoding);
+criterion_main!(ulid_lite_perf);
@@ -13,6 +13,12 @@
  */
 #define ULID_LEN 26
 
+/**
+ * Error code for when the system clock can't be read, typically because
+ * it is set to before the UNIX epoch. Functions return its negation.
+ */
+#define ULID_ECLOCK 1001
+
 /**
  * Context object for `ulid` operations
  *
@@ -20,6 +26,12 @@
  */
 typedef struct ulid_ctx ulid_ctx;
 
+/**
+ * Binary ULID, most significant byte first
+ *
+ * Comparing two `ulid` values byte by byte orders them the same
+ * way as their text form.
+ */
 typedef uint8_t ulid[ULID_BINARY_LEN];
 
 /**
@@ -38,6 +50,8 @@ void ulid_ctx_destroy(struct ulid_ctx *ctx);
  *
  * Passing 0 as `seed` will seed the random number generator from the
  * system's clock.
+ *
+ * Returns a null pointer if the system's clock can't be read.
  */
 struct ulid_ctx *ulid_init(uint32_t seed);
 
@@ -48,8 +62,27 @@ struct ulid_ctx *ulid_init(uint32_t seed);
  * from the system's clock.
  *
  * The destination `dest` must be a valid, non-null, pointer to `ulid`.
+ *
+ * Returns 0 on success, or a negative error code on failure.
+ */
+int ulid_new(struct ulid_ctx *ctx, ulid *dest);
+
+/**
+ * Create a new 128-bit ULID in `dest` for the time `timestamp_ms`,
+ * expressed in milliseconds since the UNIX epoch.
+ *
+ * Only the lowest 48 bits of `timestamp_ms` are used.
+ *
+ * If the `ctx` pointer is null, the random number generator is re-seeded
+ * from the system's clock. Otherwise, the system's clock is never read,
+ * so together with a `ctx` from `ulid_init` with a non-zero `seed`, this
+ * suits targets without a usable clock.
+ *
+ * The destination `dest` must be a valid, non-null, pointer to `ulid`.
+ *
+ * Returns 0 on success, or a negative error code on failure.
  */
-void ulid_new(struct ulid_ctx *ctx, ulid *dest);
+int ulid_new_at(struct ulid_ctx *ctx, uint64_t timestamp_ms, ulid *dest);
 
 /**
  * Write a new ULID to `dest` as a string.
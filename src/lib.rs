@@ -1,4 +1,9 @@
-use core::fmt::{Display, Formatter, LowerHex, Result, UpperHex};
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter, LowerHex, UpperHex};
+use core::iter::{FromIterator, FusedIterator, Peekable};
+use core::ops::{Add, Sub};
+use core::str::FromStr;
+use std::collections::{btree_map, btree_set, BTreeMap, BTreeSet};
 use std::time::{SystemTime, Duration};
 
 use xorshift::{Rand, Rng, SeedableRng, SplitMix64, Xoroshiro128};
@@ -12,8 +17,44 @@ pub const ULID_BINARY_LEN: usize = 16;
 /// Number of bytes for the ASCII text representation of a `ulid`
 pub const ULID_LEN: usize = 26;
 
+/// Number of bytes for the framed binary representation of a `ulid`
+///
+/// See [`Ulid::to_framed_bytes`].
+pub const ULID_FRAMED_LEN: usize = ULID_BINARY_LEN + 1;
+
+/// Version byte written by [`Ulid::to_framed_bytes`]
+pub const FRAMED_VERSION: u8 = 1;
+
+/// Value of each byte in Crockford's Base32 alphabet, or -1 for bytes
+/// outside of it
+///
+/// Both cases are accepted. As Crockford specifies, the confusable
+/// characters `I` and `L` decode as `1` and `O` as `0`, in either case,
+/// which matches [`normalize_crockford`] and lenient parsing with
+/// [`UlidParser`]. [`str::parse`] is stricter and rejects them.
+pub const BASE32_REVERSE: [i8; 256] = {
+    let mut table = [-1; 256];
+    let mut b = 0;
+    while b < 256 {
+        if let Some(value) = base32::reverse_lookup(b as u8) {
+            table[b] = value as i8; // cast is safe because value < 32
+        }
+        b += 1;
+    }
+    table[b'I' as usize] = 1;
+    table[b'i' as usize] = 1;
+    table[b'L' as usize] = 1;
+    table[b'l' as usize] = 1;
+    table[b'O' as usize] = 0;
+    table[b'o' as usize] = 0;
+    table
+};
+
+/// Selects the 80 random bits of a ULID
+const RANDOM_MASK: u128 = (1 << 80) - 1;
+
 mod base32 {
-    use super::ULID_LEN;
+    use super::{ParseError, ULID_LEN};
     use core::hint::unreachable_unchecked;
 
     #[inline]
@@ -61,266 +102,4324 @@ mod base32 {
             buffer[ULID_LEN - 1 - i] = lookup((raw_ulid & 0x1f) as u8);
             raw_ulid >>= 5;
         }
+
+        // 26 characters carry 130 bits, so the top 2 bits of the first
+        // character are always zero. `decode` rejects anything else,
+        // which keeps the two exact inverses.
+        debug_assert_eq!(raw_ulid, 0);
     }
-}
 
-#[inline]
-fn duration_since_epoch() -> Duration {
-    let now = SystemTime::now();
+    // Crockford's additional symbols for check values 32 to 36
+    const CHECK_SYMBOLS: [u8; 5] = *b"*~$=U";
 
-    now
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .expect("system clock is set to before UNIX epoch")
-}
+    /// Crockford's check symbol for `raw_ulid`, its value modulo 37
+    #[inline]
+    pub fn check_symbol(raw_ulid: u128) -> u8 {
+        let value = (raw_ulid % 37) as u8; // cast is safe because value < 37
+        match value {
+            0..=31 => lookup(value),
+            _ => CHECK_SYMBOLS[value as usize - 32],
+        }
+    }
 
-#[repr(C)]
-#[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Debug, Hash)]
-pub struct Ulid {
-    bits: u128,
-}
+    /// Encodes only the character at `index` of the canonical form
+    #[inline]
+    pub fn char_at(raw_ulid: u128, index: usize) -> u8 {
+        let shift = 5 * (ULID_LEN - 1 - index);
+        lookup(((raw_ulid >> shift) & 0x1f) as u8)
+    }
 
-impl Ulid {
     #[inline]
-    pub fn new() -> Self {
-        UlidGenerator::new().ulid()
+    #[forbid(unsafe_code)]
+    pub const fn reverse_lookup(b: u8) -> Option<u8> {
+        let value = match b {
+            b'0'..=b'9' => b - b'0',
+            b'A'..=b'H' => b - b'A' + 10,
+            b'J' | b'K' => b - b'J' + 18,
+            b'M' | b'N' => b - b'M' + 20,
+            b'P'..=b'T' => b - b'P' + 22,
+            b'V'..=b'Z' => b - b'V' + 27,
+            b'a'..=b'h' => b - b'a' + 10,
+            b'j' | b'k' => b - b'j' + 18,
+            b'm' | b'n' => b - b'm' + 20,
+            b'p'..=b't' => b - b'p' + 22,
+            b'v'..=b'z' => b - b'v' + 27,
+            _ => return None,
+        };
+        Some(value)
     }
 
-    pub fn new_nil() -> Self {
-        Ulid { bits: 0 }
+    // const so that the `ulid!` macro can decode literals at compile time.
+    //
+    // Unlike encode(), the input here is untrusted, so neither this function
+    // nor reverse_lookup() may use `unsafe`. Every byte value outside of
+    // the alphabet must be reported as an error.
+    #[forbid(unsafe_code)]
+    pub const fn decode(encoded: &[u8]) -> Result<u128, ParseError> {
+        if encoded.len() != ULID_LEN {
+            return Err(ParseError::InvalidLength { len: encoded.len() });
+        }
+
+        let mut raw_ulid: u128 = 0;
+        let mut i = 0;
+        while i < ULID_LEN {
+            let value = match reverse_lookup(encoded[i]) {
+                Some(value) => value,
+                None => return Err(ParseError::InvalidChar { position: i, byte: encoded[i] }),
+            };
+
+            // 26 characters carry 130 bits, so the first character may
+            // only use its lowest 3 bits
+            if i == 0 && value > 7 {
+                return Err(ParseError::Overflow);
+            }
+
+            raw_ulid = (raw_ulid << 5) | value as u128;
+            i += 1;
+        }
+
+        Ok(raw_ulid)
     }
-}
 
-impl Display for Ulid {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let mut buf: [u8; 26] = [0; 26];
-        base32::encode(self.bits, &mut buf);
-        // Safety: guaranteed to be UTF-8 because we control the
-        //         bytes that are present.
-        let ascii_ulid = unsafe { core::str::from_utf8_unchecked(&buf) };
-        write!(f, "{}", ascii_ulid)
+    /// Maps characters that are commonly confused with members of
+    /// Crockford's alphabet onto their canonical form and uppercases
+    /// everything else. Bytes outside the alphabet are passed through.
+    #[inline]
+    pub fn normalize(b: u8) -> u8 {
+        match b.to_ascii_uppercase() {
+            b'O' => b'0',
+            b'I' | b'L' => b'1',
+            other => other,
+        }
     }
 }
 
-impl LowerHex for Ulid {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        LowerHex::fmt(&self.bits, f)
-    }
+/// Error returned when a string can't be decoded as a `Ulid`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ParseError {
+    /// The input is not exactly 26 characters long
+    InvalidLength {
+        /// The input's length, in bytes
+        len: usize,
+    },
+
+    /// The input contains a character outside of Crockford's Base32 alphabet
+    InvalidChar {
+        /// The index of the first invalid byte, after any surrounding
+        /// whitespace or separators have been removed
+        position: usize,
+
+        /// The invalid byte. For non-ASCII characters, this is the first
+        /// byte of their UTF-8 encoding.
+        byte: u8,
+    },
+
+    /// The input encodes a value that does not fit in 128 bits
+    Overflow,
+
+    /// The input's check symbol does not match the rest of the input
+    ChecksumMismatch,
 }
 
-impl UpperHex for Ulid {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        UpperHex::fmt(&self.bits, f)
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseError::InvalidLength { len } => {
+                write!(f, "invalid length: found {} bytes", len)
+            }
+            ParseError::InvalidChar { position, byte } if byte.is_ascii_graphic() => {
+                write!(f, "invalid character {:?} at position {}", byte as char, position)
+            }
+            ParseError::InvalidChar { position, byte } => {
+                write!(f, "invalid byte 0x{:02x} at position {}", byte, position)
+            }
+            ParseError::Overflow => {
+                write!(f, "value exceeds 128 bits (the first character must be between 0 and 7)")
+            }
+            ParseError::ChecksumMismatch => {
+                write!(f, "check symbol does not match the ULID")
+            }
+        }
     }
 }
 
+impl std::error::Error for ParseError {}
 
-/// Generates ULIDs, sortable yet unique identifiers.
+/// Error returned when monotonic generation runs out of random values
+/// within a single millisecond
 ///
-/// The primary way to create a `UlidGenerator` is through `::new()`.
-/// This will seed the internal pseudo-random number generator (PRNG)
-/// with the current timestamp.
-///
-/// `UlidGenerator` implements [`std::iter::Iterator`], which allows you
-///  to create a continuous series of ULIDs.
-///
-/// They
-///
-/// ```rust
-/// use ulid::UlidGenerator;
-///
-/// let mut ulid_gen = UlidGenerator::new();
-/// let mut ulids: Vec<_> = ulid_gen.take(1000).collect();
-///
-/// let test = ulids.pop().unwrap();
-/// for ulid in ulids {
-///     assert_ne!(test, ulid);
-/// }
-/// ```
-///
-/// You can also use a fixed seed to create a repeatable sequence:
-///
-/// ```rust
-/// use ulid::UlidGenerator;
-///
-/// let ulid_gen = UlidGenerator::from_seed(12345);
-/// let ulids: Vec<_> = ulid_gen.take(5).collect();
-///
-/// // Only the low bits are affected, so we check slices near the end
-/// assert_eq!(&(ulids[0].to_string()[20..]), "RBPBCT");
-/// assert_eq!(&(ulids[4].to_string()[20..]), "BZBF00");
-/// ```
-pub struct UlidGenerator {
-    rng: Xoroshiro128,
+/// See [`UlidGenerator::next_monotonic`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct MonotonicOverflow;
+
+impl Display for MonotonicOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "random component overflowed within the same millisecond")
+    }
 }
 
-impl UlidGenerator {
+impl std::error::Error for MonotonicOverflow {}
 
-    #[inline]
-    pub fn new() -> Self {
-        let seed = (duration_since_epoch().as_nanos() & u64::MAX as u128) as u64;
-        Self::from_seed(seed)
-    }
+/// Error returned by [`UlidGenerator::ulid_checked`] and
+/// [`UlidGenerator::next_monotonic_checked`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum GenError {
+    /// The clock can't be read
+    Clock(ClockError),
 
-    #[inline]
-    pub fn from_seed(seed: u64) -> Self {
-        // Use a SplitMix64 PRNG to seed a Xoroshiro128+ PRNG
-        let mut sm: SplitMix64 = SeedableRng::from_seed(seed);
-        let rng: Xoroshiro128 = Rand::rand(&mut sm);
+    /// The timestamp is too large for 48 bits, which happens after the
+    /// year 10889
+    TimestampOverflow,
 
-        UlidGenerator {
-            rng,
+    /// Monotonic generation ran out of random values within a single
+    /// millisecond
+    MonotonicOverflow,
+}
+
+impl Display for GenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GenError::Clock(err) => write!(f, "can't read clock: {}", err),
+            GenError::TimestampOverflow => write!(f, "timestamp exceeds 48 bits"),
+            GenError::MonotonicOverflow => Display::fmt(&MonotonicOverflow, f),
         }
     }
+}
 
-    #[inline]
-    pub fn ulid(&mut self) -> Ulid {
-        Ulid {
-            bits: self.time_bits() << 80 | self.rand_bits()
+impl std::error::Error for GenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GenError::Clock(err) => Some(err),
+            _ => None,
         }
     }
+}
 
-    #[inline]
-    fn time_bits(&self) -> u128 {
-        // TODO: add OS-specific implementations that are quicker
+impl From<ClockError> for GenError {
+    fn from(err: ClockError) -> Self {
+        GenError::Clock(err)
+    }
+}
 
-        let t = duration_since_epoch();
-        t.as_millis() & (1 << 48) - 1
+impl From<MonotonicOverflow> for GenError {
+    fn from(_: MonotonicOverflow) -> Self {
+        GenError::MonotonicOverflow
     }
+}
 
-    #[inline]
-    fn rand_bits(&mut self) -> u128 {
-        let a = self.rng.next_u64() as u128;
-        let b = self.rng.next_u64() as u128;
+/// Error returned when a ULID's timestamp is later than allowed, or
+/// outside of the 48 bits that a ULID can hold
+///
+/// See [`Ulid::try_from_u128_checked`] and [`Ulid::from_storage`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct TimestampOutOfRange;
 
-        let mut bits  = a << 64 | b;
-        bits &= (1 << 80) - 1; // 0xfff...
-        bits
+impl Display for TimestampOutOfRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "timestamp is later than the permitted maximum")
     }
 }
 
-impl Iterator for UlidGenerator {
-    type Item = Ulid;
+impl std::error::Error for TimestampOutOfRange {}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        Some(self.ulid())
+/// Error returned when framed binary data has a version byte that this
+/// crate doesn't know how to read
+///
+/// The field holds the version byte that was found. See
+/// [`Ulid::from_framed_bytes`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct UnsupportedVersion(pub u8);
+
+impl Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported framed ULID version: {}", self.0)
     }
 }
 
-/// Create a unique ULID as a base32-encoded string
-///
-/// # Examples
+impl std::error::Error for UnsupportedVersion {}
+
+/// Error returned when a buffer that should hold a ULID in its low
+/// bytes has non-zero bytes above them
 ///
-/// ```rust
-/// use ulid::ulid;
+/// See [`Ulid::from_32_bytes_checked`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct NonZeroPadding;
+
+impl Display for NonZeroPadding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "padding before the ULID is not zero")
+    }
+}
+
+impl std::error::Error for NonZeroPadding {}
+
+/// Error returned when the clock can't provide a timestamp, typically
+/// because the system clock is set to before the UNIX epoch
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct ClockError;
+
+impl Display for ClockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "system clock is set to before UNIX epoch")
+    }
+}
+
+impl std::error::Error for ClockError {}
+
+/// Converts days since 1970-01-01 into a (year, month, day) date in the
+/// proleptic Gregorian calendar
 ///
-/// let a = ulid();
-/// let b = ulid();
-/// assert_ne!(a, b);
-/// ```
-pub fn ulid() -> String {
-    UlidGenerator::new().ulid().to_string()
+/// Based on Howard Hinnant's `civil_from_days` algorithm:
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // [0, 146096]
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32; // [1, 31]
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32; // [1, 12]
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
 }
 
-pub fn ulid_raw() -> u128 {
-    UlidGenerator::new().ulid().bits
+#[cfg_attr(not(feature = "profiling"), inline)]
+fn try_duration_since_epoch() -> Result<Duration, ClockError> {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|_| ClockError)
 }
 
-#[cfg(ffi)]
-mod ffi {
-    use super::*;
-    use ::libc::{c_char, c_int, size_t, ERANGE};
-    use std::slice::from_raw_parts_mut;
+#[cfg_attr(not(feature = "profiling"), inline)]
+fn duration_since_epoch() -> Duration {
+    let now = SystemTime::now();
 
-    #[allow(non_camel_case_types)]
-    pub type ulid = [u8; ULID_BINARY_LEN];
+    now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("system clock is set to before UNIX epoch")
+}
 
-    impl From<Ulid> for ulid {
-        #[inline]
-        fn from(id: Ulid) -> Self {
-            unsafe { std::mem::transmute(id.bits) }
-        }
-    }
+/// A ULID: a 48-bit timestamp followed by 80 random bits
+///
+/// Values that are created but never used trigger a warning:
+///
+/// ```compile_fail
+/// #![deny(unused_must_use)]
+///
+/// ulid::Ulid::new();
+/// ```
+#[repr(C)]
+#[must_use]
+#[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Debug, Hash)]
+pub struct Ulid {
+    bits: u128,
+}
 
-    /// Context object for `ulid` operations
-    ///
-    /// Contains information related to the internal RNG.
-    // #[repr(rust)] so that cbindgen generates an opaque struct
-    #[allow(non_camel_case_types)]
-    pub struct ulid_ctx {
-        pub(crate) gen: UlidGenerator,
+impl Ulid {
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    pub fn new() -> Self {
+        UlidGenerator::new().ulid()
     }
 
-    impl ulid_ctx {
-        #[inline]
-        #[must_use]
-        unsafe fn ensure_init(ctx: *mut ulid_ctx) -> *mut ulid_ctx {
-            if ctx.is_null() {
-                ulid_init(0)
-            } else {
-                ctx
-            }
-        }
+    pub fn new_nil() -> Self {
+        Ulid { bits: 0 }
     }
 
-    /// Destroy the `ulid_ctx` object
+    /// The binary form of the ULID in big-endian byte order
     ///
-    /// # Safety
-    ///
-    /// Must not be called on the same value twice. This results
-    /// in a double free.
-    #[no_mangle]
-    pub unsafe extern "C" fn ulid_ctx_destroy(ctx: *mut ulid_ctx) {
-        Box::from_raw(ctx); // immediately drop
+    /// This is the canonical binary layout from the ULID spec: the
+    /// timestamp comes first, so the bytes sort in the same order as
+    /// the ULIDs themselves.
+    #[inline]
+    pub const fn to_be_bytes(&self) -> [u8; ULID_BINARY_LEN] {
+        self.bits.to_be_bytes()
     }
 
-    /// Generate a `ulid_ctx` and seed the random number generator (RNG)
-    /// provided by your system's libc implementation of the rand() family.
+    /// The binary form of the ULID in little-endian byte order
     ///
-    /// Passing 0 as `seed` will seed the random number generator from the
-    /// system's clock.
-    #[no_mangle]
-    pub extern "C" fn ulid_init(seed: u32) -> *mut ulid_ctx {
-        let gen = match seed {
-            0 => super::UlidGenerator::new(),
-            s => {
-                super::UlidGenerator::from_seed(s as u64)
-            }
-        };
+    /// Prefer [`Ulid::to_be_bytes`] unless little-endian is specifically
+    /// required, as these bytes don't sort in ULID order.
+    #[inline]
+    pub const fn to_le_bytes(&self) -> [u8; ULID_BINARY_LEN] {
+        self.bits.to_le_bytes()
+    }
 
-        let ctx = ulid_ctx { gen };
-        Box::leak(Box::new(ctx))
+    /// Create a `Ulid` from its big-endian binary form
+    #[inline]
+    pub const fn from_be_bytes(bytes: [u8; ULID_BINARY_LEN]) -> Self {
+        Ulid { bits: u128::from_be_bytes(bytes) }
     }
 
-    // /// Seed the random number generator with `s`
-    // #[no_mangle]
-    // pub unsafe extern "C" fn ulid_seed(s: u32) {
-    //     seed(s);
-    // }
+    /// Create a `Ulid` from its little-endian binary form
+    #[inline]
+    pub const fn from_le_bytes(bytes: [u8; ULID_BINARY_LEN]) -> Self {
+        Ulid { bits: u128::from_le_bytes(bytes) }
+    }
 
-    /// Create a new 128-bit ULID in `dest`.
+    /// The framed binary form of the ULID, meant for long-term storage
     ///
-    /// If the `ctx` pointer is null, the random number generator is re-seeded
-    /// from the system's clock.
+    /// This is a version byte, currently [`FRAMED_VERSION`], followed by
+    /// the big-endian binary form. The version byte leaves room for the
+    /// format to change without old data becoming unreadable.
     ///
-    /// The destination `dest` must be a valid, non-null, pointer to `ulid`.
-    #[no_mangle]
-    pub unsafe extern "C" fn ulid_new(ctx: *mut ulid_ctx, dest: &mut ulid) {
-        let ctx = ulid_ctx::ensure_init(ctx);
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = Ulid::new();
+    /// let framed = id.to_framed_bytes();
+    /// assert_eq!(framed[0], ulid::FRAMED_VERSION);
+    /// assert_eq!(Ulid::from_framed_bytes(&framed), Ok(id));
+    /// ```
+    pub fn to_framed_bytes(&self) -> [u8; ULID_FRAMED_LEN] {
+        let mut framed = [0; ULID_FRAMED_LEN];
+        framed[0] = FRAMED_VERSION;
+        framed[1..].copy_from_slice(&self.to_be_bytes());
+        framed
+    }
 
-        let id: ulid = (*ctx).gen.ulid().into();
-        *dest = std::mem::transmute(id);
+    /// Create a `Ulid` from the framed binary form written by
+    /// [`Ulid::to_framed_bytes`]
+    ///
+    /// Fails if the version byte is not one that this crate can read.
+    pub fn from_framed_bytes(framed: &[u8; ULID_FRAMED_LEN]) -> Result<Self, UnsupportedVersion> {
+        match framed[0] {
+            FRAMED_VERSION => {
+                let mut bytes = [0; ULID_BINARY_LEN];
+                bytes.copy_from_slice(&framed[1..]);
+                Ok(Ulid::from_be_bytes(bytes))
+            }
+            version => Err(UnsupportedVersion(version)),
+        }
     }
 
-    /// Write a new ULID to `dest` as a string.
+    /// Create a `Ulid` from a 32-byte big-endian buffer, such as a 256-bit
+    /// integer, whose high 16 bytes must all be zero
     ///
-    /// Crockford's Base32 alphabet is used, and exactly 27 bytes are written,
-    /// including the terminating null byte.
+    /// Fails rather than dropping the high bytes, which catches callers
+    /// that wrote more than a ULID into the buffer.
     ///
-    /// The destination `dest` must be a valid, non-null, pointer to a `char`
-    /// buffer with `size` bytes, and should have at least 27 bytes.
+    /// ```rust
+    /// use ulid::Ulid;
     ///
-    /// If the `ctx` pointer is null, the random number generator is re-seeded
-    /// from the system's clock.
+    /// let id = Ulid::new();
+    /// let mut buf = [0; 32];
+    /// buf[16..].copy_from_slice(&id.to_be_bytes());
+    /// assert_eq!(Ulid::from_32_bytes_checked(buf), Ok(id));
+    ///
+    /// buf[0] = 1;
+    /// assert!(Ulid::from_32_bytes_checked(buf).is_err());
+    /// ```
+    pub fn from_32_bytes_checked(buf: [u8; 32]) -> Result<Self, NonZeroPadding> {
+        let (padding, low) = buf.split_at(ULID_BINARY_LEN);
+        if padding.iter().any(|&b| b != 0) {
+            return Err(NonZeroPadding);
+        }
+
+        let mut bytes = [0; ULID_BINARY_LEN];
+        bytes.copy_from_slice(low);
+        Ok(Ulid::from_be_bytes(bytes))
+    }
+
+    /// Create a `Ulid` from binary data of unknown byte order, such as
+    /// data written by older releases of the FFI layer, which used
+    /// native byte order
+    ///
+    /// The byte order is chosen by plausibility: the one whose timestamp
+    /// falls between the years 2000 and 2100 wins. When both or neither
+    /// do, big-endian is assumed.
+    ///
+    /// This is a heuristic for migrating data, not a substitute for
+    /// knowing the byte order. Read the wrong way, random bytes still
+    /// produce a plausible timestamp about 1% of the time, which makes
+    /// little-endian data look ambiguous and so be read as big-endian.
+    /// ULIDs with timestamps outside of that range are always read as
+    /// big-endian.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(Ulid::from_bytes_autodetect(&id.to_be_bytes()), id);
+    /// assert_eq!(Ulid::from_bytes_autodetect(&id.to_le_bytes()), id);
+    /// ```
+    pub fn from_bytes_autodetect(bytes: &[u8; ULID_BINARY_LEN]) -> Self {
+        // 2000-01-01 and 2100-01-01
+        const PLAUSIBLE: core::ops::Range<u64> = 946_684_800_000..4_102_444_800_000;
+
+        let be = Ulid::from_be_bytes(*bytes);
+        let le = Ulid::from_le_bytes(*bytes);
+        if !PLAUSIBLE.contains(&be.timestamp_ms()) && PLAUSIBLE.contains(&le.timestamp_ms()) {
+            le
+        } else {
+            be
+        }
+    }
+
+    /// Split the ULID into its high and low 64 bits
+    ///
+    /// The high half holds the 48-bit timestamp followed by the first 16
+    /// random bits. The low half holds the remaining 64 random bits.
+    /// Comparing `(hi, lo)` tuples gives the same order as comparing ULIDs.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = Ulid::new();
+    /// let (hi, lo) = id.split_u64s();
+    /// assert_eq!(hi >> 16, id.timestamp_ms());
+    /// assert_eq!(Ulid::from_u64s(hi, lo), id);
+    /// ```
+    #[inline]
+    pub const fn split_u64s(&self) -> (u64, u64) {
+        ((self.bits >> 64) as u64, self.bits as u64)
+    }
+
+    /// Reassemble a ULID from the halves returned by [`Ulid::split_u64s`]
+    #[inline]
+    pub const fn from_u64s(hi: u64, lo: u64) -> Self {
+        Ulid { bits: ((hi as u128) << 64) | lo as u128 }
+    }
+
+    /// Split the ULID into four 32-bit words, most significant first
+    ///
+    /// The first word and the top half of the second hold the timestamp.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = Ulid::new();
+    /// assert_eq!(Ulid::from_words(id.to_words()), id);
+    /// ```
+    #[inline]
+    pub const fn to_words(&self) -> [u32; 4] {
+        [
+            (self.bits >> 96) as u32,
+            (self.bits >> 64) as u32,
+            (self.bits >> 32) as u32,
+            self.bits as u32,
+        ]
+    }
+
+    /// Reassemble a ULID from four 32-bit words, most significant first,
+    /// as returned by [`Ulid::to_words`]
+    #[inline]
+    pub const fn from_words(words: [u32; 4]) -> Self {
+        Ulid {
+            bits: (words[0] as u128) << 96
+                | (words[1] as u128) << 64
+                | (words[2] as u128) << 32
+                | words[3] as u128,
+        }
+    }
+
+    /// Split the ULID into a timestamp and random bytes for storing in
+    /// separate database columns
+    ///
+    /// The timestamp is in milliseconds since the UNIX epoch, as SQL
+    /// databases commonly store `BIGINT`, and the 80 random bits are in
+    /// big-endian order. Sorting by the timestamp and then the bytes
+    /// gives the same order as sorting the ULIDs.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let (ts, random) = id.split_for_storage();
+    /// assert_eq!(ts, 1469922850259);
+    /// assert_eq!(Ulid::from_storage(ts, random), Ok(id));
+    /// ```
+    pub fn split_for_storage(&self) -> (i64, [u8; 10]) {
+        let mut random = [0; 10];
+        random.copy_from_slice(&self.to_be_bytes()[6..]);
+        (self.timestamp_ms() as i64, random) // cast is safe because the timestamp fits in 48 bits
+    }
+
+    /// Reassemble a ULID from the columns returned by
+    /// [`Ulid::split_for_storage`]
+    ///
+    /// Fails if `ts` is negative or doesn't fit in 48 bits, rather than
+    /// silently storing a different time.
+    pub fn from_storage(ts: i64, random: [u8; 10]) -> Result<Self, TimestampOutOfRange> {
+        if !(0..1 << 48).contains(&ts) {
+            return Err(TimestampOutOfRange);
+        }
+
+        let mut bytes = [0; ULID_BINARY_LEN];
+        bytes[..6].copy_from_slice(&ts.to_be_bytes()[2..]);
+        bytes[6..].copy_from_slice(&random);
+        Ok(Ulid::from_be_bytes(bytes))
+    }
+
+    /// Create a ULID from `bits`, checking that its timestamp is no later
+    /// than `max_timestamp_ms`
+    ///
+    /// Every `u128` is a valid ULID, but a timestamp far in the future is
+    /// a good sign that the value was corrupted, for example in storage.
+    ///
+    /// ```rust
+    /// use ulid::{TimestampOutOfRange, Ulid};
+    ///
+    /// let bits = 1469922850259 << 80;
+    /// assert!(Ulid::try_from_u128_checked(bits, 1469922850259).is_ok());
+    /// assert_eq!(Ulid::try_from_u128_checked(bits, 1469922850258), Err(TimestampOutOfRange));
+    /// ```
+    pub fn try_from_u128_checked(bits: u128, max_timestamp_ms: u64) -> Result<Self, TimestampOutOfRange> {
+        let id = Ulid { bits };
+        if id.timestamp_ms() > max_timestamp_ms {
+            return Err(TimestampOutOfRange);
+        }
+        Ok(id)
+    }
+
+    /// The number of distinct ULIDs within one millisecond, 2^80
+    #[inline]
+    pub const fn max_per_millisecond() -> u128 {
+        1 << 80
+    }
+
+    /// A hash of the ULID that is the same on every run and every
+    /// platform, for reproducible output such as golden files
+    ///
+    /// This is 64-bit FNV-1a over the big-endian bytes. Unlike [`Hash`]
+    /// with the standard library's `RandomState`, it isn't seeded, so it
+    /// offers no protection against deliberately colliding keys. The
+    /// value is guaranteed not to change between releases.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(id.fixed_hash(), 0x4cc5_4d9b_81e1_83a0);
+    /// ```
+    pub fn fixed_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        self.to_be_bytes()
+            .iter()
+            .fold(OFFSET_BASIS, |hash, &b| (hash ^ b as u64).wrapping_mul(PRIME))
+    }
+
+    /// Map the ULID onto one of `num_shards` shards, from 0 to
+    /// `num_shards - 1`
+    ///
+    /// Only the random component is used, after mixing its bits, so
+    /// ULIDs from the same moment are spread evenly. Sharding by the
+    /// timestamp prefix, or by a range of ULIDs, would send everything
+    /// created around the same time to the same shard. The result is
+    /// stable, so it is safe to persist.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is 0.
+    ///
+    /// ```rust
+    /// let id = ulid::Ulid::new();
+    /// assert!(id.shard(8) < 8);
+    /// ```
+    pub fn shard(&self, num_shards: u32) -> u32 {
+        assert!(num_shards > 0, "num_shards must be at least 1");
+
+        // the finalizer of SplitMix64, over all 80 random bits
+        let random = self.random();
+        let mut h = (random as u64) ^ ((random >> 64) as u64).rotate_left(32);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+
+        (h % num_shards as u64) as u32 // cast is safe because the result is below num_shards
+    }
+
+    /// The absolute difference between the two ULIDs as numbers
+    ///
+    /// This is only meaningful for sequences that count up, such as those
+    /// from [`UlidGenerator::next_monotonic`] within one millisecond or
+    /// from [`Ulid::from_timestamp_and_counter`], where it gives the
+    /// number of IDs issued in between. For random ULIDs it is noise.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let a = Ulid::from_timestamp_and_counter(1469922850259, 10);
+    /// let b = Ulid::from_timestamp_and_counter(1469922850259, 25);
+    /// assert_eq!(a.distance(&b), 15);
+    /// assert_eq!(b.distance(&a), 15);
+    /// ```
+    #[inline]
+    pub fn distance(&self, other: &Ulid) -> u128 {
+        self.bits.max(other.bits) - self.bits.min(other.bits)
+    }
+
+    /// The ULID `n` places after this one, or `None` if that would go
+    /// past the largest ULID
+    ///
+    /// `n` is added to the random component, and carries into the
+    /// timestamp when it runs out, so `a.nth_after(a.distance(&b))` is `b`
+    /// whenever `a <= b`.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let a = Ulid::from_timestamp_and_counter(1469922850259, 10);
+    /// assert_eq!(a.nth_after(15), Some(Ulid::from_timestamp_and_counter(1469922850259, 25)));
+    /// assert_eq!(Ulid::from_u64s(u64::MAX, u64::MAX).nth_after(1), None);
+    /// ```
+    #[inline]
+    pub fn nth_after(&self, n: u128) -> Option<Ulid> {
+        self.bits.checked_add(n).map(|bits| Ulid { bits })
+    }
+
+    /// Compare so that newer ULIDs come first, for use with
+    /// [`slice::sort_by`]
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let mut ids: Vec<Ulid> = ulid::UlidGenerator::new().take(10).collect();
+    /// ids.sort_by(Ulid::cmp_newest_first);
+    /// assert!(ids.windows(2).all(|pair| pair[0] >= pair[1]));
+    /// ```
+    #[inline]
+    pub fn cmp_newest_first(a: &Ulid, b: &Ulid) -> Ordering {
+        b.cmp(a)
+    }
+
+    /// Check for equality in constant time, for ULIDs that act as secrets
+    /// such as bearer tokens
+    ///
+    /// `==` may return as soon as it finds a difference, which can leak
+    /// how much of a guessed ULID was right through timing. This compares
+    /// every byte regardless. Only available with the `subtle` feature.
+    ///
+    /// ```rust
+    /// let token = ulid::Ulid::new();
+    /// assert!(token.ct_eq(&token));
+    /// ```
+    #[cfg(feature = "subtle")]
+    #[inline]
+    pub fn ct_eq(&self, other: &Ulid) -> bool {
+        subtle::ConstantTimeEq::ct_eq(self, other).into()
+    }
+
+    /// Compare by timestamp, then by the random component
+    ///
+    /// This is the canonical ULID ordering. It is the same as [`Ord`], as
+    /// well as comparing the canonical strings or the big-endian bytes,
+    /// and is guaranteed to stay that way.
+    ///
+    /// ```rust
+    /// use std::cmp::Ordering;
+    ///
+    /// let older = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let newer = ulid::ulid!("01BX5ZZKBKACTAV9WEVGEMMVS0");
+    /// assert_eq!(older.total_cmp(&newer), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn total_cmp(&self, other: &Ulid) -> Ordering {
+        self.timestamp_ms()
+            .cmp(&other.timestamp_ms())
+            .then_with(|| self.random().cmp(&other.random()))
+    }
+
+    /// The most recent ULID in `ids`, or `None` if it is empty
+    ///
+    /// ```rust
+    /// use ulid::{ulid, Ulid};
+    ///
+    /// let ids = vec![ulid!("01BX5ZZKBKACTAV9WEVGEMMVS0"), ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV")];
+    /// assert_eq!(Ulid::latest(ids), Some(ulid!("01BX5ZZKBKACTAV9WEVGEMMVS0")));
+    /// ```
+    pub fn latest<I: IntoIterator<Item = Ulid>>(ids: I) -> Option<Ulid> {
+        ids.into_iter().max()
+    }
+
+    /// The oldest ULID in `ids`, or `None` if it is empty
+    pub fn earliest<I: IntoIterator<Item = Ulid>>(ids: I) -> Option<Ulid> {
+        ids.into_iter().min()
+    }
+
+    /// Check that `ids` is strictly increasing, as monotonic generation
+    /// guarantees
+    ///
+    /// Returns the index of the first ULID that isn't greater than the
+    /// one before it.
+    ///
+    /// ```rust
+    /// use ulid::{Ulid, UlidGenerator};
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// let mut ids: Vec<Ulid> = (0..3).map(|_| ulid_gen.next_monotonic().unwrap()).collect();
+    /// assert_eq!(Ulid::validate_monotonic_sequence(&ids), Ok(()));
+    ///
+    /// ids.swap(1, 2);
+    /// assert_eq!(Ulid::validate_monotonic_sequence(&ids), Err(2));
+    /// ```
+    pub fn validate_monotonic_sequence(ids: &[Ulid]) -> Result<(), usize> {
+        match ids.windows(2).position(|pair| pair[0] >= pair[1]) {
+            Some(i) => Err(i + 1),
+            None => Ok(()),
+        }
+    }
+
+    /// The probability that at least two of `count` ULIDs created within
+    /// the same millisecond collide
+    ///
+    /// This is the birthday bound for 80 random bits,
+    /// `1 - e^(-count * (count - 1) / 2^81)`. It stays below one in a
+    /// million until about 1.5 billion ULIDs. Monotonic generation never
+    /// collides within a single generator.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// assert!(Ulid::collision_probability(1_000_000) < 1e-12);
+    /// ```
+    pub fn collision_probability(count: u64) -> f64 {
+        let count = count as f64;
+        let pairs = count * (count - 1.0) / 2.0;
+        let expected = pairs / Ulid::max_per_millisecond() as f64;
+
+        // exp_m1 keeps precision for the tiny probabilities that are typical
+        -(-expected.max(0.0)).exp_m1()
+    }
+
+    /// The smallest ULID with the timestamp `ms`, with all random bits unset
+    ///
+    /// Together with [`Ulid::max_for_timestamp`], this gives the bounds for
+    /// selecting ULIDs by time from sorted collections.
+    #[inline]
+    pub const fn min_for_timestamp(ms: u64) -> Self {
+        let time_bits = ms as u128 & ((1 << 48) - 1);
+        Ulid { bits: time_bits << 80 }
+    }
+
+    /// The largest ULID with the timestamp `ms`, with all random bits set
+    #[inline]
+    pub const fn max_for_timestamp(ms: u64) -> Self {
+        let time_bits = ms as u128 & ((1 << 48) - 1);
+        Ulid { bits: (time_bits << 80) | RANDOM_MASK }
+    }
+
+    /// A ULID with the timestamp `ms` and `counter` in its lowest 64 bits,
+    /// for sequential IDs that are entirely deterministic
+    ///
+    /// **These ULIDs contain no randomness**, so anyone who sees one can
+    /// guess the others. Use them only where reproducibility matters more
+    /// than unpredictability. The 16 bits above the counter are zero, and
+    /// only the lowest 48 bits of `ms` are used. IDs with the same `ms`
+    /// sort by `counter`.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let first = Ulid::from_timestamp_and_counter(1469922850259, 0);
+    /// let second = Ulid::from_timestamp_and_counter(1469922850259, 1);
+    /// assert!(first < second);
+    /// ```
+    #[inline]
+    pub const fn from_timestamp_and_counter(ms: u64, counter: u64) -> Self {
+        let time_bits = ms as u128 & ((1 << 48) - 1);
+        Ulid { bits: (time_bits << 80) | counter as u128 }
+    }
+
+    /// The time that the ULID's timestamp represents
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(id.datetime(), UNIX_EPOCH + Duration::from_millis(1469922850259));
+    /// ```
+    #[inline]
+    pub fn datetime(&self) -> SystemTime {
+        self.datetime_with_epoch(SystemTime::UNIX_EPOCH)
+    }
+
+    /// The time that the ULID's timestamp represents, for a ULID created
+    /// by a generator using [`UlidGenerator::with_epoch`]
+    #[inline]
+    pub fn datetime_with_epoch(&self, epoch: SystemTime) -> SystemTime {
+        epoch + Duration::from_millis(self.timestamp_ms())
+    }
+
+    /// Whether the ULID's timestamp is more than `tolerance_ms` ahead of
+    /// the system clock
+    ///
+    /// This is useful for validating ULIDs from outside sources, which
+    /// shouldn't claim to have been created in the future. The tolerance
+    /// allows for clock skew between machines.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// assert!(!Ulid::new().is_future(1_000));
+    /// assert!(Ulid::min_for_timestamp(1 << 47).is_future(1_000));
+    /// ```
+    pub fn is_future(&self, tolerance_ms: u64) -> bool {
+        // A clock set to before the UNIX epoch is treated as the epoch
+        let now_ms = try_duration_since_epoch().map_or(0, |now| now.as_millis());
+        self.timestamp_ms() as u128 > now_ms + tolerance_ms as u128
+    }
+
+    /// The ULID's timestamp as an ISO 8601 string in UTC, with
+    /// millisecond precision
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(id.timestamp_iso8601(), "2016-07-30T23:54:10.259Z");
+    /// ```
+    pub fn timestamp_iso8601(&self) -> String {
+        let ms = self.timestamp_ms();
+        let secs = ms / 1_000;
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let secs_of_day = secs % 86_400;
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            secs_of_day / 3_600,
+            secs_of_day % 3_600 / 60,
+            secs_of_day % 60,
+            ms % 1_000,
+        )
+    }
+
+    /// Milliseconds since the UNIX epoch encoded in the ULID's timestamp
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(id.timestamp_ms(), 1469922850259);
+    /// ```
+    #[inline]
+    pub fn timestamp_ms(&self) -> u64 {
+        (self.bits >> 80) as u64 // cast is safe because only 48 bits remain
+    }
+
+    /// The 80 random bits of the ULID
+    #[inline]
+    pub fn random(&self) -> u128 {
+        self.bits & RANDOM_MASK
+    }
+
+    /// A copy of the ULID with its timestamp replaced by `ms`, keeping
+    /// the random component
+    ///
+    /// Only the lowest 48 bits of `ms` are used.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let moved = id.with_timestamp(1_000);
+    /// assert_eq!(moved.timestamp_ms(), 1_000);
+    /// assert_eq!(moved.random(), id.random());
+    /// ```
+    #[inline]
+    pub const fn with_timestamp(&self, ms: u64) -> Ulid {
+        let time_bits = ms as u128 & ((1 << 48) - 1);
+        Ulid { bits: (time_bits << 80) | (self.bits & RANDOM_MASK) }
+    }
+
+    /// A copy of the ULID with the random bits selected by `mask` taken
+    /// from `value`, for embedding metadata in the random component
+    ///
+    /// `value` is in place, not shifted: bit `n` of the result comes from
+    /// bit `n` of `value` if bit `n` of `mask` is set, and from `self`
+    /// otherwise. The timestamp is left alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mask` has any of the 48 timestamp bits set.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let tagged = id.with_random_mask(0xff, 0x2a);
+    /// assert_eq!(tagged.random() & 0xff, 0x2a);
+    /// assert_eq!(tagged.random() >> 8, id.random() >> 8);
+    /// assert_eq!(tagged.timestamp_ms(), id.timestamp_ms());
+    /// ```
+    #[inline]
+    pub fn with_random_mask(&self, mask: u128, value: u128) -> Ulid {
+        assert!(mask & !RANDOM_MASK == 0, "mask must not touch the timestamp bits");
+        Ulid { bits: (self.bits & !mask) | (value & mask) }
+    }
+
+    /// A copy of the ULID one millisecond later, keeping the random
+    /// component, or `None` if the timestamp is already the largest that
+    /// fits in 48 bits
+    ///
+    /// This gives an exclusive upper bound just past a ULID's millisecond
+    /// for range scans, together with [`Ulid::min_for_timestamp`].
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let later = id.try_increment_timestamp().unwrap();
+    /// assert_eq!(later.timestamp_ms(), id.timestamp_ms() + 1);
+    /// assert_eq!(later.random(), id.random());
+    /// ```
+    #[inline]
+    pub fn try_increment_timestamp(&self) -> Option<Ulid> {
+        match self.timestamp_ms() + 1 {
+            ms if ms < 1 << 48 => Some(self.with_timestamp(ms)),
+            _ => None,
+        }
+    }
+
+    /// Split the ULID into its timestamp and random components
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let parts = id.components();
+    /// assert_eq!(parts.timestamp_ms, id.timestamp_ms());
+    /// assert_eq!(parts.random, id.random());
+    /// ```
+    #[inline]
+    pub fn components(&self) -> UlidComponents {
+        UlidComponents {
+            timestamp_ms: self.timestamp_ms(),
+            random: self.random(),
+        }
+    }
+
+    /// Whole seconds since the UNIX epoch, rounded down
+    #[inline]
+    pub fn timestamp_seconds(&self) -> u64 {
+        self.timestamp_ms() / 1_000
+    }
+
+    /// Nanoseconds since the UNIX epoch
+    ///
+    /// ULIDs only have millisecond precision, so the value is always
+    /// a multiple of 1,000,000.
+    #[inline]
+    pub fn timestamp_nanos(&self) -> u128 {
+        self.timestamp_ms() as u128 * 1_000_000
+    }
+
+    /// Write the 10 timestamp characters of the canonical string form
+    /// into `buf`, without allocating
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let mut buf = [0; 10];
+    /// assert_eq!(id.timestamp_str(&mut buf), "01ARZ3NDEK");
+    /// ```
+    pub fn timestamp_str<'a>(&self, buf: &'a mut [u8; TIMESTAMP_LEN]) -> &'a str {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = base32::char_at(self.bits, i);
+        }
+        // Safety: guaranteed to be UTF-8 because we control the
+        //         bytes that are present.
+        unsafe { core::str::from_utf8_unchecked(buf) }
+    }
+
+    /// Returns true if the canonical string form of this ULID starts
+    /// with `prefix`
+    ///
+    /// Only as many characters as `prefix` contains are encoded, so no
+    /// allocation takes place. Matching is case-sensitive against the
+    /// canonical uppercase form.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert!(id.starts_with("01ARZ3"));
+    /// assert!(!id.starts_with("01ARZ4"));
+    /// ```
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        if prefix.len() > ULID_LEN {
+            return false;
+        }
+
+        prefix
+            .bytes()
+            .enumerate()
+            .all(|(i, b)| base32::char_at(self.bits, i) == b)
+    }
+
+    /// Find every ULID embedded in `buf`, such as raw log data
+    ///
+    /// A ULID is recognized as a run of exactly 26 characters from the
+    /// Base32 alphabet (in either case) that is not part of a longer run.
+    /// Runs that don't decode, because they overflow 128 bits, are
+    /// skipped. The buffer doesn't need to be valid UTF-8.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let log = b"\xff 2016-07-30 id=01ARZ3NDEKTSV4RRFFQ69G5FAV ok";
+    /// let found = Ulid::scan_bytes(log);
+    /// assert_eq!(found, vec![ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV")]);
+    /// ```
+    pub fn scan_bytes(buf: &[u8]) -> Vec<Ulid> {
+        buf.split(|&b| base32::reverse_lookup(b).is_none())
+            .filter(|run| run.len() == ULID_LEN)
+            .filter_map(|run| base32::decode(run).ok())
+            .map(|bits| Ulid { bits })
+            .collect()
+    }
+
+    /// Compare this ULID against the ULID string `s` without decoding `s`
+    ///
+    /// `self` is encoded and compared byte by byte, which gives the same
+    /// result as decoding `s` because Base32 preserves order. This is
+    /// useful for bisecting sorted files of ULID strings. `s` is still
+    /// validated and may use either case.
+    ///
+    /// ```rust
+    /// use std::cmp::Ordering;
+    ///
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(id.cmp_str("01ARZ3NDEKTSV4RRFFQ69G5FAW"), Ok(Ordering::Less));
+    /// ```
+    pub fn cmp_str(&self, s: &str) -> Result<Ordering, ParseError> {
+        if s.len() != ULID_LEN {
+            return Err(ParseError::InvalidLength { len: s.len() });
+        }
+
+        let mut buf = [0; ULID_LEN];
+        base32::encode(self.bits, &mut buf);
+
+        let mut ordering = Ordering::Equal;
+        for (i, (&ours, theirs)) in buf.iter().zip(s.bytes()).enumerate() {
+            let value = base32::reverse_lookup(theirs)
+                .ok_or(ParseError::InvalidChar { position: i, byte: theirs })?;
+            if i == 0 && value > 7 {
+                return Err(ParseError::Overflow);
+            }
+            if ordering == Ordering::Equal {
+                ordering = ours.cmp(&theirs.to_ascii_uppercase());
+            }
+        }
+
+        Ok(ordering)
+    }
+
+    /// Format the ULID with Crockford's check symbol appended, giving 27
+    /// characters
+    ///
+    /// The check symbol is the ULID's value modulo 37, so any single
+    /// mistyped character is detected by [`Ulid::from_str_with_check`].
+    /// This is not a canonical ULID: other implementations won't accept it.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let checked = id.to_string_with_check();
+    /// assert_eq!(checked.len(), 27);
+    /// assert_eq!(ulid::Ulid::from_str_with_check(&checked), Ok(id));
+    /// ```
+    pub fn to_string_with_check(&self) -> String {
+        let mut buf = [0; ULID_LEN + 1];
+        base32::encode(self.bits, &mut buf);
+        buf[ULID_LEN] = base32::check_symbol(self.bits);
+        buf.iter().map(|&b| b as char).collect()
+    }
+
+    /// Write the canonical text form into `w` without allocating
+    ///
+    /// This is what [`Display`] uses, and suits building up many ULIDs in
+    /// one buffer.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let mut out = String::from("id=");
+    /// id.write_canonical(&mut out).unwrap();
+    /// assert_eq!(out, "id=01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// ```
+    pub fn write_canonical<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let buf = <[u8; ULID_LEN]>::from(*self);
+        // Safety: guaranteed to be UTF-8 because we control the
+        //         bytes that are present.
+        let ascii_ulid = unsafe { core::str::from_utf8_unchecked(&buf) };
+        w.write_str(ascii_ulid)
+    }
+
+    /// Append the canonical text form to `out` as 26 ASCII bytes
+    ///
+    /// This suits building large export buffers, such as CSV files,
+    /// without making a `String` for every ULID.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// let mut row = b"id,".to_vec();
+    /// id.append_to(&mut row);
+    /// assert_eq!(row, b"id,01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// ```
+    pub fn append_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&<[u8; ULID_LEN]>::from(*self));
+    }
+
+    /// Parse a `Ulid` followed by its check symbol, as produced by
+    /// [`Ulid::to_string_with_check`]
+    ///
+    /// Like [`str::parse`], either case is accepted. A check symbol that
+    /// doesn't match is reported as [`ParseError::ChecksumMismatch`].
+    pub fn from_str_with_check(s: &str) -> Result<Self, ParseError> {
+        let bytes = s.as_bytes();
+        if bytes.len() != ULID_LEN + 1 {
+            return Err(ParseError::InvalidLength { len: bytes.len() });
+        }
+
+        let bits = base32::decode(&bytes[..ULID_LEN])?;
+        if bytes[ULID_LEN].to_ascii_uppercase() != base32::check_symbol(bits) {
+            return Err(ParseError::ChecksumMismatch);
+        }
+        Ok(Ulid { bits })
+    }
+
+    /// Format the ULID with `sep` inserted between every `group_size`
+    /// characters, counting from the left
+    ///
+    /// Grouping makes ULIDs easier to read aloud. A `group_size` of 0
+    /// disables grouping. Use [`Ulid::parse_grouped`] to read the
+    /// result back in.
+    ///
+    /// ```rust
+    /// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// assert_eq!(id.to_grouped_string(8, '-'), "01ARZ3ND-EKTSV4RR-FFQ69G5F-AV");
+    /// assert_eq!(id.to_grouped_string(4, ' '), "01AR Z3ND EKTS V4RR FFQ6 9G5F AV");
+    /// ```
+    pub fn to_grouped_string(&self, group_size: usize, sep: char) -> String {
+        let mut buf = [0; ULID_LEN];
+        base32::encode(self.bits, &mut buf);
+
+        if group_size == 0 {
+            return buf.iter().map(|&b| b as char).collect();
+        }
+
+        let mut out = String::with_capacity(ULID_LEN + ULID_LEN / group_size * sep.len_utf8());
+        for (i, chunk) in buf.chunks(group_size).enumerate() {
+            if i > 0 {
+                out.push(sep);
+            }
+            out.extend(chunk.iter().map(|&b| b as char));
+        }
+        out
+    }
+
+    /// Parse a `Ulid` that may contain `sep` between its characters, as
+    /// produced by [`Ulid::to_grouped_string`]
+    ///
+    /// Every occurrence of `sep` is removed before decoding.
+    pub fn parse_grouped(s: &str, sep: char) -> Result<Self, ParseError> {
+        let mut buf = [0; ULID_LEN];
+        let mut len = 0;
+
+        for c in s.chars().filter(|&c| c != sep) {
+            if len < ULID_LEN {
+                let mut utf8 = [0; 4];
+                buf[len] = c.encode_utf8(&mut utf8).as_bytes()[0];
+            }
+            len += c.len_utf8();
+        }
+
+        if len != ULID_LEN {
+            return Err(ParseError::InvalidLength { len });
+        }
+
+        let bits = base32::decode(&buf)?;
+        Ok(Ulid { bits })
+    }
+
+    /// Parse a `Ulid`, ignoring any leading or trailing ASCII whitespace
+    ///
+    /// This is a tolerant alternative to [`str::parse`], which is useful
+    /// for input read from files or typed by users. Whitespace within the
+    /// ULID itself is still rejected.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = Ulid::parse_trimmed("  01ARZ3NDEKTSV4RRFFQ69G5FAV\n").unwrap();
+    /// assert_eq!(id.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// ```
+    pub fn parse_trimmed(s: &str) -> Result<Self, ParseError> {
+        s.trim_matches(|c: char| c.is_ascii_whitespace()).parse()
+    }
+
+    /// Parse a `Ulid` from a buffer that may be nul-terminated, as
+    /// written by the FFI's `ulid_write`, or end in a newline, as when
+    /// read from a file
+    ///
+    /// Everything from the first nul byte onwards is ignored, and then a
+    /// single trailing `\n` or `\r\n`.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let buf = *b"01ARZ3NDEKTSV4RRFFQ69G5FAV\0";
+    /// let id = Ulid::parse_nul_terminated(&buf).unwrap();
+    /// assert_eq!(id.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// ```
+    pub fn parse_nul_terminated(buf: &[u8]) -> Result<Self, ParseError> {
+        let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        let mut bytes = &buf[..end];
+        if let Some(line) = bytes.strip_suffix(b"\n") {
+            bytes = line.strip_suffix(b"\r").unwrap_or(line);
+        }
+
+        Ok(Ulid { bits: base32::decode(bytes)? })
+    }
+
+    /// Rewrite a leniently typed ULID in the exact canonical form: 26
+    /// uppercase characters
+    ///
+    /// Surrounding whitespace, lowercase letters and the confusable
+    /// characters `O`, `I` and `L` are all accepted, as by a
+    /// [`UlidParser`] with every option on. This is the normalization
+    /// step before storing user-entered or legacy ULIDs in a
+    /// case-sensitive index.
+    ///
+    /// ```rust
+    /// let canonical = ulid::Ulid::canonicalize(" 01arz3ndektsv4rrffq69g5fav\n").unwrap();
+    /// assert_eq!(canonical, "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    /// ```
+    pub fn canonicalize(s: &str) -> Result<String, ParseError> {
+        let parser = UlidParser::new().normalize_confusables(true).trim(true);
+        parser.parse(s).map(|id| id.to_string())
+    }
+
+    /// Decode a batch of strings, such as a column being imported, with one
+    /// result for each input in the same order
+    ///
+    /// A bad input doesn't stop the rest from being decoded. Each string
+    /// is decoded as by [`str::parse`].
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let results = Ulid::decode_many(vec!["01ARZ3NDEKTSV4RRFFQ69G5FAV", "oops"]);
+    /// assert!(results[0].is_ok());
+    /// assert!(results[1].is_err());
+    /// ```
+    pub fn decode_many<'a>(items: impl IntoIterator<Item = &'a str>) -> Vec<Result<Self, ParseError>> {
+        items
+            .into_iter()
+            .map(|s| base32::decode(s.as_bytes()).map(|bits| Ulid { bits }))
+            .collect()
+    }
+
+    /// Decode the 26 characters of a ULID directly from bytes, such as a
+    /// network buffer, without checking that they are UTF-8 first
+    ///
+    /// This accepts exactly what [`str::parse`] accepts.
+    ///
+    /// ```rust
+    /// let id = ulid::Ulid::from_base32_bytes(b"01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap();
+    /// assert_eq!(id.timestamp_ms(), 1469922850259);
+    /// ```
+    #[inline]
+    pub const fn from_base32_bytes(bytes: &[u8; ULID_LEN]) -> Result<Self, ParseError> {
+        match base32::decode(bytes) {
+            Ok(bits) => Ok(Ulid { bits }),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Parse `s` into `dest`, reusing an existing `Ulid`
+    ///
+    /// This behaves like [`str::parse`], which is handy when filling a
+    /// preallocated buffer. `dest` is left unchanged on error.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let mut ids = vec![Ulid::new_nil(); 2];
+    /// for (text, id) in ["01ARZ3NDEKTSV4RRFFQ69G5FAV", "01BX5ZZKBKACTAV9WEVGEMMVS0"].iter().zip(&mut ids) {
+    ///     Ulid::from_str_radix32_into(text, id).unwrap();
+    /// }
+    /// assert!(ids[0] < ids[1]);
+    /// ```
+    #[inline]
+    pub fn from_str_radix32_into(s: &str, dest: &mut Ulid) -> Result<(), ParseError> {
+        dest.bits = base32::decode(s.as_bytes())?;
+        Ok(())
+    }
+
+    /// Parse a `Ulid` that is in canonical form, exactly as the encoder
+    /// would write it
+    ///
+    /// Unlike [`str::parse`], which accepts either case, lowercase
+    /// characters are rejected with [`ParseError::InvalidChar`]. Use this
+    /// to find stored IDs that need normalizing.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// assert!(Ulid::from_str_canonical("01ARZ3NDEKTSV4RRFFQ69G5FAV").is_ok());
+    /// assert!(Ulid::from_str_canonical("01arz3ndektsv4rrffq69g5fav").is_err());
+    /// ```
+    pub fn from_str_canonical(s: &str) -> Result<Self, ParseError> {
+        let bits = base32::decode(s.as_bytes())?;
+        if let Some(position) = s.bytes().position(|b| b.is_ascii_lowercase()) {
+            return Err(ParseError::InvalidChar { position, byte: s.as_bytes()[position] });
+        }
+        Ok(Ulid { bits })
+    }
+
+    /// Parse a `Ulid` from its hexadecimal representation
+    ///
+    /// This is the inverse of the `LowerHex` and `UpperHex` formatting.
+    /// An optional `0x` prefix is accepted, followed by between 1 and 32
+    /// hexadecimal digits of either case.
+    ///
+    /// ```rust
+    /// use ulid::Ulid;
+    ///
+    /// let id = Ulid::new();
+    /// assert_eq!(Ulid::from_hex(&format!("{:#x}", id)), Ok(id));
+    /// ```
+    pub fn from_hex(s: &str) -> Result<Self, ParseError> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+
+        if digits.is_empty() || digits.len() > 32 {
+            return Err(ParseError::InvalidLength { len: digits.len() });
+        }
+
+        if let Some(position) = digits.bytes().position(|b| !b.is_ascii_hexdigit()) {
+            return Err(ParseError::InvalidChar { position, byte: digits.as_bytes()[position] });
+        }
+
+        // every digit is valid and there are at most 32, so this can't fail
+        let bits = u128::from_str_radix(digits, 16).map_err(|_| ParseError::Overflow)?;
+        Ok(Ulid { bits })
+    }
+}
+
+impl Ulid {
+    // Used by the `ulid!` macro. Panicking inside a const context
+    // turns invalid literals into compile errors.
+    #[doc(hidden)]
+    pub const fn __from_literal(s: &str) -> Self {
+        match base32::decode(s.as_bytes()) {
+            Ok(bits) => Ulid { bits },
+            Err(ParseError::InvalidLength { .. }) => panic!("ULID literal must be 26 characters long"),
+            Err(ParseError::InvalidChar { .. }) => panic!("ULID literal contains an invalid character"),
+            Err(ParseError::Overflow) => panic!("ULID literal exceeds 128 bits"),
+            // decode() has no check symbol to verify
+            Err(ParseError::ChecksumMismatch) => unreachable!(),
+        }
+    }
+}
+
+/// Create a `Ulid` from a string literal at compile time
+///
+/// The literal is decoded while compiling, so typos are reported as
+/// build errors rather than at runtime. The macro can be used to
+/// initialize constants.
+///
+/// ```rust
+/// use ulid::Ulid;
+///
+/// const SENTINEL: Ulid = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// assert_eq!(SENTINEL.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// ```
+///
+/// Invalid literals don't compile:
+///
+/// ```compile_fail
+/// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAU");
+/// ```
+#[macro_export]
+macro_rules! ulid {
+    ($s:literal) => {{
+        const ULID: $crate::Ulid = $crate::Ulid::__from_literal($s);
+        ULID
+    }};
+}
+
+/// The two fields of a ULID, as returned by [`Ulid::components`]
+///
+/// With the `serde` feature enabled, the random component is serialized
+/// as a 16 character Crockford Base32 string, because not every format
+/// supports 128-bit integers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct UlidComponents {
+    /// Milliseconds since the UNIX epoch. Only the lowest 48 bits are used.
+    pub timestamp_ms: u64,
+
+    /// Random bits. Only the lowest 80 bits are used.
+    pub random: u128,
+}
+
+impl From<UlidComponents> for Ulid {
+    fn from(parts: UlidComponents) -> Self {
+        let time_bits = parts.timestamp_ms as u128 & ((1 << 48) - 1);
+        Ulid {
+            bits: (time_bits << 80) | (parts.random & RANDOM_MASK),
+        }
+    }
+}
+
+impl FromStr for Ulid {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bits = base32::decode(s.as_bytes())?;
+        Ok(Ulid { bits })
+    }
+}
+
+/// Number of characters used by the timestamp in the text representation
+const TIMESTAMP_LEN: usize = 10;
+
+/// A `Ulid` that sorts newest first
+///
+/// Its [`Ord`] is the reverse of `Ulid`'s, so collections of it, such as
+/// feeds or logs, put the most recent entries at the front. See also
+/// [`Ulid::cmp_newest_first`].
+///
+/// ```rust
+/// use ulid::{NewestFirst, Ulid};
+///
+/// let older = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// let newer = ulid::ulid!("01BX5ZZKBKACTAV9WEVGEMMVS0");
+///
+/// let mut feed = vec![NewestFirst(older), NewestFirst(newer)];
+/// feed.sort();
+/// assert_eq!(feed, [NewestFirst(newer), NewestFirst(older)]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct NewestFirst(pub Ulid);
+
+impl Ord for NewestFirst {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl PartialOrd for NewestFirst {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<Ulid> for NewestFirst {
+    fn from(id: Ulid) -> Self {
+        NewestFirst(id)
+    }
+}
+
+impl From<NewestFirst> for Ulid {
+    fn from(id: NewestFirst) -> Self {
+        id.0
+    }
+}
+
+/// A shortened, non-canonical ULID that keeps the timestamp but only
+/// some of the randomness
+///
+/// The text form of a `ShortUlid` is a prefix of the canonical 26
+/// character string: the 10 timestamp characters followed by between
+/// 0 and 16 random characters. Like a normal ULID, it sorts by time.
+///
+/// Each random character carries 5 bits, so `n` random characters give
+/// `2^(5n)` possible values per millisecond, and collisions become
+/// likely after roughly `2^(5n / 2)` IDs in the same millisecond. That
+/// is about 1,000 IDs for 4 characters, compared to 2^40 for a full
+/// ULID. Only use this for internal IDs where that trade-off is known
+/// to be acceptable, as other ULID implementations won't accept them.
+///
+/// ```rust
+/// use ulid::{ShortUlid, Ulid};
+///
+/// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// let short = ShortUlid::new(id, 6);
+/// assert_eq!(short.to_string(), "01ARZ3NDEKTSV4RR");
+/// assert_eq!("01ARZ3NDEKTSV4RR".parse(), Ok(short));
+/// ```
+#[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Debug, Hash)]
+pub struct ShortUlid {
+    id: Ulid,
+    random_chars: u8,
+}
+
+impl ShortUlid {
+    /// Keep `id`'s timestamp and the first `random_chars` characters of
+    /// its random component
+    ///
+    /// # Panics
+    ///
+    /// Panics if `random_chars` is greater than 16.
+    pub fn new(id: Ulid, random_chars: usize) -> Self {
+        assert!(random_chars <= ULID_LEN - TIMESTAMP_LEN, "a ULID has 16 random characters");
+
+        let dropped_bits = 5 * (ULID_LEN - TIMESTAMP_LEN - random_chars);
+        let bits = id.bits >> dropped_bits << dropped_bits;
+        ShortUlid {
+            id: Ulid { bits },
+            random_chars: random_chars as u8, // cast is safe because random_chars <= 16
+        }
+    }
+
+    /// The number of random characters that are kept
+    pub fn random_chars(&self) -> usize {
+        self.random_chars as usize
+    }
+
+    /// Milliseconds since the UNIX epoch
+    pub fn timestamp_ms(&self) -> u64 {
+        self.id.timestamp_ms()
+    }
+
+    /// Expand into a full `Ulid`, with the dropped random bits set to zero
+    pub fn to_ulid(&self) -> Ulid {
+        self.id
+    }
+}
+
+impl Display for ShortUlid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut buf: [u8; 26] = [0; 26];
+        base32::encode(self.id.bits, &mut buf);
+        let len = TIMESTAMP_LEN + self.random_chars();
+        // Safety: guaranteed to be UTF-8 because we control the
+        //         bytes that are present.
+        let ascii = unsafe { core::str::from_utf8_unchecked(&buf[..len]) };
+        write!(f, "{}", ascii)
+    }
+}
+
+impl FromStr for ShortUlid {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() < TIMESTAMP_LEN || s.len() > ULID_LEN {
+            return Err(ParseError::InvalidLength { len: s.len() });
+        }
+
+        let mut buf = [b'0'; ULID_LEN];
+        buf[..s.len()].copy_from_slice(s.as_bytes());
+        let bits = base32::decode(&buf)?;
+
+        Ok(ShortUlid {
+            id: Ulid { bits },
+            random_chars: (s.len() - TIMESTAMP_LEN) as u8, // cast is safe because of the length check
+        })
+    }
+}
+
+/// A sorted set of ULIDs
+///
+/// As ULIDs sort by time, iteration runs from the oldest to the newest
+/// and ranges of time can be selected efficiently.
+///
+/// ```rust
+/// use ulid::{UlidGenerator, UlidSet};
+///
+/// let mut ulid_gen = UlidGenerator::new();
+/// let mut set = UlidSet::new();
+/// set.insert(ulid_gen.ulid_at(1000));
+/// set.insert(ulid_gen.ulid_at(2000));
+/// set.insert(ulid_gen.ulid_at(3000));
+///
+/// assert_eq!(set.range_ms(1500, 3000).count(), 2);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default, Hash)]
+pub struct UlidSet {
+    inner: BTreeSet<Ulid>,
+}
+
+impl UlidSet {
+    pub fn new() -> Self {
+        UlidSet { inner: BTreeSet::new() }
+    }
+
+    /// Adds `id` to the set, returning false if it was already present
+    pub fn insert(&mut self, id: Ulid) -> bool {
+        self.inner.insert(id)
+    }
+
+    /// Generate a ULID with `ulid_gen`, add it to the set and return it
+    pub fn insert_new<R: Rng, C: Clock>(&mut self, ulid_gen: &mut UlidGenerator<R, C>) -> Ulid {
+        let id = ulid_gen.ulid();
+        self.inner.insert(id);
+        id
+    }
+
+    pub fn remove(&mut self, id: &Ulid) -> bool {
+        self.inner.remove(id)
+    }
+
+    pub fn contains(&self, id: &Ulid) -> bool {
+        self.inner.contains(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The oldest ULID in the set
+    pub fn first(&self) -> Option<&Ulid> {
+        self.inner.iter().next()
+    }
+
+    /// The newest ULID in the set
+    pub fn last(&self) -> Option<&Ulid> {
+        self.inner.iter().next_back()
+    }
+
+    /// Iterate from the oldest to the newest ULID
+    pub fn iter(&self) -> btree_set::Iter<'_, Ulid> {
+        self.inner.iter()
+    }
+
+    /// Iterate over the ULIDs with timestamps between `start_ms` and
+    /// `end_ms`, inclusive
+    pub fn range_ms(&self, start_ms: u64, end_ms: u64) -> btree_set::Range<'_, Ulid> {
+        let start = Ulid::min_for_timestamp(start_ms);
+        if start_ms > end_ms {
+            return self.inner.range(start..start);
+        }
+        self.inner.range(start..=Ulid::max_for_timestamp(end_ms))
+    }
+}
+
+impl Extend<Ulid> for UlidSet {
+    fn extend<I: IntoIterator<Item = Ulid>>(&mut self, iter: I) {
+        self.inner.extend(iter);
+    }
+}
+
+impl FromIterator<Ulid> for UlidSet {
+    fn from_iter<I: IntoIterator<Item = Ulid>>(iter: I) -> Self {
+        UlidSet { inner: iter.into_iter().collect() }
+    }
+}
+
+impl IntoIterator for UlidSet {
+    type Item = Ulid;
+    type IntoIter = btree_set::IntoIter<Ulid>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a UlidSet {
+    type Item = &'a Ulid;
+    type IntoIter = btree_set::Iter<'a, Ulid>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.iter()
+    }
+}
+
+/// A sorted map keyed by ULIDs that can be queried by time
+///
+/// Like [`UlidSet`], entries run from the oldest to the newest. The time
+/// queries pick the right ULID bounds for whole milliseconds, so there's
+/// no need to build them with [`Ulid::min_for_timestamp`] and
+/// [`Ulid::max_for_timestamp`] by hand.
+///
+/// ```rust
+/// use ulid::{UlidGenerator, UlidTimeIndex};
+///
+/// let mut ulid_gen = UlidGenerator::new();
+/// let mut index = UlidTimeIndex::new();
+/// index.insert(ulid_gen.ulid_at(1000), "first");
+/// index.insert(ulid_gen.ulid_at(2000), "second");
+/// index.insert(ulid_gen.ulid_at(3000), "third");
+///
+/// let values: Vec<_> = index.range_time(1500, 3000).map(|(_, v)| *v).collect();
+/// assert_eq!(values, ["second", "third"]);
+/// assert_eq!(index.since(3000).count(), 1);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct UlidTimeIndex<V> {
+    inner: BTreeMap<Ulid, V>,
+}
+
+impl<V> UlidTimeIndex<V> {
+    pub fn new() -> Self {
+        UlidTimeIndex { inner: BTreeMap::new() }
+    }
+
+    /// Adds `value` under `id`, returning the value that it replaced
+    pub fn insert(&mut self, id: Ulid, value: V) -> Option<V> {
+        self.inner.insert(id, value)
+    }
+
+    pub fn get(&self, id: &Ulid) -> Option<&V> {
+        self.inner.get(id)
+    }
+
+    pub fn remove(&mut self, id: &Ulid) -> Option<V> {
+        self.inner.remove(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate from the oldest to the newest entry
+    pub fn iter(&self) -> btree_map::Iter<'_, Ulid, V> {
+        self.inner.iter()
+    }
+
+    /// Iterate over the entries with timestamps between `start_ms` and
+    /// `end_ms`, inclusive
+    pub fn range_time(&self, start_ms: u64, end_ms: u64) -> btree_map::Range<'_, Ulid, V> {
+        let start = Ulid::min_for_timestamp(start_ms);
+        if start_ms > end_ms {
+            return self.inner.range(start..start);
+        }
+        self.inner.range(start..=Ulid::max_for_timestamp(end_ms))
+    }
+
+    /// Iterate over the entries with timestamps of `ms` or later
+    pub fn since(&self, ms: u64) -> btree_map::Range<'_, Ulid, V> {
+        self.inner.range(Ulid::min_for_timestamp(ms)..)
+    }
+}
+
+impl<V> Default for UlidTimeIndex<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FromIterator<(Ulid, V)> for UlidTimeIndex<V> {
+    fn from_iter<I: IntoIterator<Item = (Ulid, V)>>(iter: I) -> Self {
+        UlidTimeIndex { inner: iter.into_iter().collect() }
+    }
+}
+
+impl<V> IntoIterator for UlidTimeIndex<V> {
+    type Item = (Ulid, V);
+    type IntoIter = btree_map::IntoIter<Ulid, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a UlidTimeIndex<V> {
+    type Item = (&'a Ulid, &'a V);
+    type IntoIter = btree_map::Iter<'a, Ulid, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.iter()
+    }
+}
+
+/// Parses ULIDs with configurable leniency
+///
+/// [`UlidParser::new`] accepts exactly what [`str::parse`] accepts: 26
+/// characters from Crockford's Base32 alphabet, in either case. Each
+/// option can then be switched on or off independently.
+///
+/// ```rust
+/// use ulid::UlidParser;
+///
+/// let parser = UlidParser::new()
+///     .normalize_confusables(true)
+///     .trim(true);
+///
+/// let id = parser.parse(" O1ARZ3NDEKTSV4RRFFQ69G5FAV\n").unwrap();
+/// assert_eq!(id.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct UlidParser {
+    case_insensitive: bool,
+    normalize_confusables: bool,
+    trim: bool,
+}
+
+impl UlidParser {
+    pub fn new() -> Self {
+        UlidParser {
+            case_insensitive: true,
+            normalize_confusables: false,
+            trim: false,
+        }
+    }
+
+    /// Accept lowercase letters. On by default, as the ULID spec is
+    /// case insensitive. When off, only uppercase input is accepted.
+    pub fn case_insensitive(mut self, enabled: bool) -> Self {
+        self.case_insensitive = enabled;
+        self
+    }
+
+    /// Read `O` as `0` and `I` and `L` as `1`, as [`normalize_crockford`]
+    /// does. Off by default.
+    pub fn normalize_confusables(mut self, enabled: bool) -> Self {
+        self.normalize_confusables = enabled;
+        self
+    }
+
+    /// Ignore leading and trailing ASCII whitespace. Off by default.
+    pub fn trim(mut self, enabled: bool) -> Self {
+        self.trim = enabled;
+        self
+    }
+
+    pub fn parse(&self, s: &str) -> Result<Ulid, ParseError> {
+        let s = if self.trim {
+            s.trim_matches(|c: char| c.is_ascii_whitespace())
+        } else {
+            s
+        };
+
+        if s.len() != ULID_LEN {
+            return Err(ParseError::InvalidLength { len: s.len() });
+        }
+
+        if !self.case_insensitive {
+            if let Some(position) = s.bytes().position(|b| b.is_ascii_lowercase()) {
+                return Err(ParseError::InvalidChar { position, byte: s.as_bytes()[position] });
+            }
+        }
+
+        let mut buf = [0; ULID_LEN];
+        buf.copy_from_slice(s.as_bytes());
+        if self.normalize_confusables {
+            for b in buf.iter_mut() {
+                *b = base32::normalize(*b);
+            }
+        }
+
+        // report the byte as it was written, rather than normalized
+        let bits = base32::decode(&buf).map_err(|err| match err {
+            ParseError::InvalidChar { position, .. } => {
+                ParseError::InvalidChar { position, byte: s.as_bytes()[position] }
+            }
+            other => other,
+        })?;
+        Ok(Ulid { bits })
+    }
+}
+
+impl Default for UlidParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The nil ULID, so that `#[derive(Default)]` doesn't quietly mint a new
+/// ID. Use [`Ulid::new`] for a fresh one.
+impl Default for Ulid {
+    fn default() -> Self {
+        Self::new_nil()
+    }
+}
+
+// Writes the bytes straight to the formatter rather than through
+// `write!`, skipping a layer of formatting machinery. Width and
+// alignment have never been applied, and still aren't.
+impl Display for Ulid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_canonical(f)
+    }
+}
+
+impl From<Ulid> for [u8; ULID_LEN] {
+    /// The canonical text form as ASCII bytes
+    fn from(id: Ulid) -> Self {
+        let mut buf = [0; ULID_LEN];
+        base32::encode(id.bits, &mut buf);
+        buf
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for Ulid {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.to_be_bytes().ct_eq(&other.to_be_bytes())
+    }
+}
+
+impl From<Ulid> for String {
+    /// The canonical text form
+    fn from(id: Ulid) -> Self {
+        id.to_string()
+    }
+}
+
+impl From<&Ulid> for String {
+    /// The canonical text form
+    fn from(id: &Ulid) -> Self {
+        id.to_string()
+    }
+}
+
+/// Compares against the big-endian binary form
+impl PartialEq<[u8; ULID_BINARY_LEN]> for Ulid {
+    fn eq(&self, other: &[u8; ULID_BINARY_LEN]) -> bool {
+        self.to_be_bytes() == *other
+    }
+}
+
+/// Compares against the canonical text form as ASCII bytes, which is
+/// uppercase
+impl PartialEq<[u8; ULID_LEN]> for Ulid {
+    fn eq(&self, other: &[u8; ULID_LEN]) -> bool {
+        <[u8; ULID_LEN]>::from(*self) == *other
+    }
+}
+
+impl LowerHex for Ulid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        LowerHex::fmt(&self.bits, f)
+    }
+}
+
+impl UpperHex for Ulid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        UpperHex::fmt(&self.bits, f)
+    }
+}
+
+/// Move the timestamp later by whole milliseconds, keeping the random
+/// component
+///
+/// Any sub-millisecond part of the duration is ignored. The timestamp
+/// saturates at the largest value that fits in 48 bits.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// let id = ulid::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// let later = id + Duration::from_secs(60);
+/// assert_eq!(later.timestamp_ms(), id.timestamp_ms() + 60_000);
+/// ```
+impl Add<Duration> for Ulid {
+    type Output = Ulid;
+
+    fn add(self, rhs: Duration) -> Ulid {
+        let ms = (self.timestamp_ms() as u128).saturating_add(rhs.as_millis());
+        let ms = ms.min((1 << 48) - 1) as u64; // cast is safe because ms fits in 48 bits
+        self.with_timestamp(ms)
+    }
+}
+
+/// Move the timestamp earlier by whole milliseconds, keeping the random
+/// component
+///
+/// Any sub-millisecond part of the duration is ignored. The timestamp
+/// saturates at the UNIX epoch.
+impl Sub<Duration> for Ulid {
+    type Output = Ulid;
+
+    fn sub(self, rhs: Duration) -> Ulid {
+        let ms = (self.timestamp_ms() as u128).saturating_sub(rhs.as_millis());
+        self.with_timestamp(ms as u64) // cast is safe because ms can only shrink
+    }
+}
+
+thread_local! {
+    static THREAD_GEN: core::cell::RefCell<UlidGenerator> = core::cell::RefCell::new(UlidGenerator::new());
+}
+
+/// Create a ULID for the time `t` with fresh random bits, taken from a
+/// generator that is kept for each thread
+///
+/// Sub-millisecond precision is dropped. Times before the UNIX epoch
+/// produce the nil ULID, and times after the year 10889 saturate at the
+/// largest 48-bit timestamp.
+///
+/// ```rust
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+/// use ulid::Ulid;
+///
+/// let t = UNIX_EPOCH + Duration::from_millis(1469922850259);
+/// assert_eq!(Ulid::from(t).timestamp_ms(), 1469922850259);
+/// ```
+impl From<SystemTime> for Ulid {
+    fn from(t: SystemTime) -> Self {
+        let ms = match t.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since_epoch) => since_epoch.as_millis().min((1 << 48) - 1) as u64, // cast is safe because ms fits in 48 bits
+            Err(_) => return Ulid::new_nil(),
+        };
+        THREAD_GEN.with(|ulid_gen| ulid_gen.borrow_mut().ulid_at(ms))
+    }
+}
+
+
+/// ULIDs tagged with the type of entity that they identify
+///
+/// `Id<User>` and `Id<Order>` are distinct types, so mixing them up is a
+/// compile error, but both share the `Ulid` machinery underneath:
+///
+/// ```rust
+/// use ulid::typed::Id;
+///
+/// struct User;
+/// struct Order;
+///
+/// let user: Id<User> = Id::new();
+/// let order: Id<Order> = Id::new();
+/// assert_eq!(user.to_string().len(), order.to_string().len());
+/// ```
+///
+/// ```compile_fail
+/// use ulid::typed::Id;
+///
+/// struct User;
+/// struct Order;
+///
+/// let user: Id<User> = Id::new();
+/// let order: Id<Order> = user;
+/// ```
+///
+/// The marker type is never stored, so it needs no trait implementations
+/// of its own.
+pub mod typed {
+    use super::{ParseError, Ulid};
+    use core::cmp::Ordering;
+    use core::fmt::{self, Debug, Display, Formatter};
+    use core::hash::{Hash, Hasher};
+    use core::marker::PhantomData;
+    use core::str::FromStr;
+
+    /// A [`Ulid`] that identifies a `T`
+    #[repr(transparent)]
+    pub struct Id<T> {
+        id: Ulid,
+        // fn() -> T keeps Id<T> Send, Sync and covariant whatever T is
+        marker: PhantomData<fn() -> T>,
+    }
+
+    impl<T> Id<T> {
+        /// Create a new ID for the current time
+        #[inline]
+        pub fn new() -> Self {
+            Id::from_ulid(Ulid::new())
+        }
+
+        /// Tag an existing `Ulid`
+        #[inline]
+        pub const fn from_ulid(id: Ulid) -> Self {
+            Id { id, marker: PhantomData }
+        }
+
+        /// The untagged `Ulid`
+        #[inline]
+        pub const fn ulid(&self) -> Ulid {
+            self.id
+        }
+    }
+
+    impl<T> Default for Id<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T> From<Ulid> for Id<T> {
+        fn from(id: Ulid) -> Self {
+            Id::from_ulid(id)
+        }
+    }
+
+    impl<T> From<Id<T>> for Ulid {
+        fn from(id: Id<T>) -> Self {
+            id.id
+        }
+    }
+
+    // Implemented by hand, as deriving would require T to implement them too
+
+    impl<T> Clone for Id<T> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<T> Copy for Id<T> {}
+
+    impl<T> PartialEq for Id<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl<T> Eq for Id<T> {}
+
+    impl<T> PartialOrd for Id<T> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl<T> Ord for Id<T> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.id.cmp(&other.id)
+        }
+    }
+
+    impl<T> Hash for Id<T> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.id.hash(state)
+        }
+    }
+
+    impl<T> Debug for Id<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Id").field(&self.id).finish()
+        }
+    }
+
+    impl<T> Display for Id<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            Display::fmt(&self.id, f)
+        }
+    }
+
+    impl<T> FromStr for Id<T> {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Id::from_ulid)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<T> serde::Serialize for Id<T> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.id.serialize(serializer)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de, T> serde::Deserialize<'de> for Id<T> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ulid::deserialize(deserializer).map(Id::from_ulid)
+        }
+    }
+}
+
+/// A source of timestamps for a [`UlidGenerator`]
+///
+/// The default, [`SystemClock`], reads the system's wall clock. Implement
+/// this trait to control time, for example in simulations and tests.
+pub trait Clock {
+    /// Milliseconds since the UNIX epoch
+    fn now_ms(&self) -> u64;
+
+    /// Milliseconds since the UNIX epoch, or an error instead of a panic
+    /// if the clock can't be read
+    ///
+    /// The default implementation calls [`Clock::now_ms`].
+    fn try_now_ms(&self) -> Result<u64, ClockError> {
+        Ok(self.now_ms())
+    }
+
+    /// Microseconds since the UNIX epoch, used for sub-millisecond
+    /// ordering
+    ///
+    /// The default implementation has millisecond resolution.
+    fn now_micros(&self) -> u64 {
+        self.now_ms().saturating_mul(1_000)
+    }
+
+    /// Microseconds since the UNIX epoch, or an error instead of a panic
+    /// if the clock can't be read
+    ///
+    /// The default implementation has millisecond resolution.
+    fn try_now_micros(&self) -> Result<u64, ClockError> {
+        Ok(self.try_now_ms()?.saturating_mul(1_000))
+    }
+}
+
+/// A [`Clock`] that reads the system's wall clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn now_ms(&self) -> u64 {
+        // TODO: add OS-specific implementations that are quicker
+        duration_since_epoch().as_millis() as u64
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn try_now_ms(&self) -> Result<u64, ClockError> {
+        Ok(try_duration_since_epoch()?.as_millis() as u64)
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn now_micros(&self) -> u64 {
+        duration_since_epoch().as_micros() as u64
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn try_now_micros(&self) -> Result<u64, ClockError> {
+        Ok(try_duration_since_epoch()?.as_micros() as u64)
+    }
+}
+
+/// A [`Clock`] that always reads the same time, in milliseconds since the
+/// UNIX epoch
+///
+/// Used by [`UlidGenerator::new_with_fixed_timestamp`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn now_ms(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A [`Clock`] that starts at a fixed time and moves forward by a fixed
+/// step every time that it is read
+///
+/// Used by [`UlidGenerator::deterministic`]. Every reading counts,
+/// including those made by [`UlidGenerator::peek_timestamp`].
+#[derive(Clone, Debug)]
+pub struct SteppingClock {
+    next_ms: core::cell::Cell<u64>,
+    step_ms: u64,
+}
+
+impl SteppingClock {
+    /// A clock that first reads `start_ms`, then `start_ms + step_ms`, and
+    /// so on
+    pub fn new(start_ms: u64, step_ms: u64) -> Self {
+        SteppingClock {
+            next_ms: core::cell::Cell::new(start_ms),
+            step_ms,
+        }
+    }
+}
+
+impl Clock for SteppingClock {
+    fn now_ms(&self) -> u64 {
+        let now = self.next_ms.get();
+        self.next_ms.set(now.saturating_add(self.step_ms));
+        now
+    }
+}
+
+/// The pseudo-random number generator used by default: xoroshiro128+
+#[derive(Clone)]
+pub struct DefaultRng(Xoroshiro128);
+
+impl DefaultRng {
+    /// Seed the PRNG, as [`UlidGenerator::from_seed`] does
+    pub fn from_seed(seed: u64) -> Self {
+        // Use a SplitMix64 PRNG to seed a Xoroshiro128+ PRNG
+        let mut sm: SplitMix64 = SeedableRng::from_seed(seed);
+        DefaultRng(Rand::rand(&mut sm))
+    }
+}
+
+impl Rng for DefaultRng {
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+/// Adapts a closure that returns random `u64` values into an [`Rng`]
+///
+/// Created by [`UlidGenerator::from_closure`].
+#[derive(Clone)]
+pub struct FnRng<F>(F);
+
+impl<F: FnMut() -> u64> Rng for FnRng<F> {
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn next_u32(&mut self) -> u32 {
+        (self.0)() as u32 // truncation keeps the lowest 32 random bits
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn next_u64(&mut self) -> u64 {
+        (self.0)()
+    }
+}
+
+/// Generates ULIDs, sortable yet unique identifiers.
+///
+/// The primary way to create a `UlidGenerator` is through `::new()`.
+/// This will seed the internal pseudo-random number generator (PRNG)
+/// with the current timestamp.
+///
+/// `UlidGenerator` implements [`std::iter::Iterator`], which allows you
+///  to create a continuous series of ULIDs.
+///
+/// They
+///
+/// ```rust
+/// use ulid::UlidGenerator;
+///
+/// let mut ulid_gen = UlidGenerator::new();
+/// let mut ulids: Vec<_> = ulid_gen.take(1000).collect();
+///
+/// let test = ulids.pop().unwrap();
+/// for ulid in ulids {
+///     assert_ne!(test, ulid);
+/// }
+/// ```
+///
+/// You can also use a fixed seed to create a repeatable sequence:
+///
+/// ```rust
+/// use ulid::UlidGenerator;
+///
+/// let ulid_gen = UlidGenerator::from_seed(12345);
+/// let ulids: Vec<_> = ulid_gen.take(5).collect();
+///
+/// // Only the low bits are affected, so we check a slice near the end
+/// assert_eq!(&(ulids[0].to_string()[20..]), "RBPBCT");
+/// ```
+///
+/// Later ULIDs made within the same millisecond increment the first one,
+/// so how many share its random component depends on the clock. See
+/// [`UlidGenerator::ulid`]. Fixing the clock as well makes the whole
+/// sequence repeatable:
+///
+/// ```rust
+/// use ulid::{DefaultRng, FixedClock, UlidGenerator};
+///
+/// let rng = DefaultRng::from_seed(12345);
+/// let ulid_gen = UlidGenerator::from_rng_and_clock(rng, FixedClock(1469922850259));
+/// let ulids: Vec<_> = ulid_gen.take(5).collect();
+///
+/// // The clock never moves on, so the fifth ULID is the first plus four
+/// assert_eq!(&(ulids[0].to_string()[20..]), "RBPBCT");
+/// assert_eq!(&(ulids[4].to_string()[20..]), "RBPBCY");
+/// ```
+///
+/// Both the PRNG and the clock can be replaced, which makes the output
+/// entirely reproducible. See [`UlidGenerator::from_rng_and_clock`].
+/// The type parameters default to [`DefaultRng`] and [`SystemClock`], so
+/// `UlidGenerator` on its own refers to the standard configuration.
+///
+/// Cloning a `UlidGenerator` forks its PRNG state, so both copies go on
+/// to produce the same random components.
+#[derive(Clone)]
+pub struct UlidGenerator<R = DefaultRng, C = SystemClock> {
+    rng: R,
+    clock: C,
+    previous: Option<Ulid>,
+    node_id: Option<u16>,
+    submillis: bool,
+    epoch_ms: u64,
+    stats: GenStats,
+    last_clock_ms: Option<u128>,
+    last_stamped: Option<((u128, u128), Ulid)>,
+    clock_skew: bool,
+    reserve_sentinels: bool,
+    recent: Option<RecentWindow>,
+}
+
+/// The most recently generated ULIDs, for spotting duplicates
+#[derive(Clone)]
+struct RecentWindow {
+    size: usize,
+    order: std::collections::VecDeque<Ulid>,
+    members: std::collections::HashSet<Ulid>,
+    saw_duplicate: bool,
+}
+
+impl RecentWindow {
+    fn new(size: usize) -> Self {
+        let size = size.max(1);
+        RecentWindow {
+            size,
+            order: std::collections::VecDeque::with_capacity(size),
+            members: std::collections::HashSet::with_capacity(size),
+            saw_duplicate: false,
+        }
+    }
+
+    fn record(&mut self, id: Ulid) {
+        if !self.members.insert(id) {
+            self.saw_duplicate = true;
+            return;
+        }
+
+        self.order.push_back(id);
+        if self.order.len() > self.size {
+            if let Some(oldest) = self.order.pop_front() {
+                self.members.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Counters describing a generator's activity, for monitoring
+///
+/// See [`UlidGenerator::stats`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct GenStats {
+    /// The number of ULIDs produced, as by [`UlidGenerator::generated_count`]
+    pub generated: u64,
+
+    /// How often monotonic generation incremented the previous ULID
+    /// rather than stamping a new one, because the clock hadn't moved on
+    pub monotonic_increments: u64,
+
+    /// How often the clock reported an earlier time than on the previous
+    /// reading
+    pub clock_backwards: u64,
+}
+
+impl UlidGenerator {
+
+    #[inline]
+    pub fn new() -> Self {
+        let seed = (duration_since_epoch().as_nanos() & u64::MAX as u128) as u64;
+        Self::from_seed(seed)
+    }
+
+    /// Like [`UlidGenerator::new`], but returns an error rather than
+    /// panicking if the system clock is set to before the UNIX epoch
+    pub fn try_new() -> Result<Self, ClockError> {
+        let seed = (try_duration_since_epoch()?.as_nanos() & u64::MAX as u128) as u64;
+        Ok(Self::from_seed(seed))
+    }
+
+    /// Seed the PRNG from the operating system's entropy source, falling
+    /// back to the current time
+    ///
+    /// OS entropy is only used when the `getrandom` feature is enabled.
+    /// If it is disabled, or the entropy source is unavailable (some
+    /// sandboxes block it), this behaves like [`UlidGenerator::new`].
+    pub fn new_from_entropy_or_clock() -> Self {
+        #[cfg(feature = "getrandom")]
+        {
+            let mut seed = [0; 8];
+            if getrandom::getrandom(&mut seed).is_ok() {
+                return Self::from_seed(u64::from_ne_bytes(seed));
+            }
+        }
+
+        Self::new()
+    }
+
+    #[inline]
+    pub fn from_seed(seed: u64) -> Self {
+        UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(seed), SystemClock)
+    }
+}
+
+impl UlidGenerator<DefaultRng, SteppingClock> {
+    /// A generator whose output is the same on every run, for tests that
+    /// compare against snapshots
+    ///
+    /// The PRNG has a fixed seed, and the clock starts at the UNIX epoch
+    /// and moves forward by one millisecond per ULID, so the timestamp
+    /// characters simply count up.
+    ///
+    /// ```rust
+    /// let mut ulid_gen = ulid::UlidGenerator::deterministic();
+    /// let ids: Vec<String> = ulid_gen.take(3).map(|id| id.to_string()).collect();
+    /// assert!(ids[0].starts_with("0000000000"));
+    /// assert!(ids[1].starts_with("0000000001"));
+    /// assert!(ids[2].starts_with("0000000002"));
+    /// ```
+    pub fn deterministic() -> Self {
+        UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(0), SteppingClock::new(0, 1))
+    }
+}
+
+impl UlidGenerator<DefaultRng, FixedClock> {
+    /// A generator whose ULIDs all carry the timestamp `ms`, which makes
+    /// same-millisecond behaviour easy to test
+    ///
+    /// The PRNG is seeded as by [`UlidGenerator::new`]. As the clock
+    /// never moves on, each ULID increments the previous one, see
+    /// [`UlidGenerator::ulid`].
+    ///
+    /// ```rust
+    /// let mut ulid_gen = ulid::UlidGenerator::new_with_fixed_timestamp(1469922850259);
+    /// let a = ulid_gen.ulid();
+    /// let b = ulid_gen.ulid();
+    /// assert_eq!(a.timestamp_ms(), b.timestamp_ms());
+    /// assert_ne!(a, b);
+    /// ```
+    pub fn new_with_fixed_timestamp(ms: u64) -> Self {
+        let seed = (duration_since_epoch().as_nanos() & u64::MAX as u128) as u64;
+        UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(seed), FixedClock(ms))
+    }
+}
+
+impl<F: FnMut() -> u64> UlidGenerator<FnRng<F>> {
+    /// Create a generator that takes its randomness from the closure `f`
+    ///
+    /// This avoids implementing [`Rng`] for one-off entropy sources, such
+    /// as a hardware RNG wrapper or a test stub. `f` is called twice per
+    /// ULID, and the 80 random bits are the lowest 16 bits of the first
+    /// value followed by all 64 bits of the second.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::from_closure(|| 0);
+    /// assert_eq!(ulid_gen.ulid().random(), 0);
+    /// ```
+    pub fn from_closure(f: F) -> Self {
+        UlidGenerator::from_rng_and_clock(FnRng(f), SystemClock)
+    }
+}
+
+impl<R: Rng, C: Clock> UlidGenerator<R, C> {
+    /// Create a generator that takes its randomness from `rng` and its
+    /// timestamps from `clock`
+    ///
+    /// When both are deterministic, so is every generated ULID.
+    ///
+    /// ```rust
+    /// use ulid::{Clock, UlidGenerator};
+    /// use xorshift::{SeedableRng, Xoroshiro128};
+    ///
+    /// struct Frozen;
+    ///
+    /// impl Clock for Frozen {
+    ///     fn now_ms(&self) -> u64 {
+    ///         1469922850259
+    ///     }
+    /// }
+    ///
+    /// let rng: Xoroshiro128 = SeedableRng::from_seed(&[1, 2][..]);
+    /// let mut ulid_gen = UlidGenerator::from_rng_and_clock(rng, Frozen);
+    /// assert_eq!(ulid_gen.ulid().timestamp_ms(), 1469922850259);
+    /// ```
+    #[inline]
+    pub fn from_rng_and_clock(rng: R, clock: C) -> Self {
+        UlidGenerator {
+            rng,
+            clock,
+            previous: None,
+            node_id: None,
+            submillis: false,
+            epoch_ms: 0,
+            stats: GenStats::default(),
+            last_clock_ms: None,
+            last_stamped: None,
+            clock_skew: false,
+            reserve_sentinels: false,
+            recent: None,
+        }
+    }
+
+    /// Generate a new ULID
+    ///
+    /// When the clock reads the same as it did for the previous ULID, as
+    /// happens on platforms whose clocks are coarser than a millisecond,
+    /// the previous ULID's random component is incremented by one rather
+    /// than drawn afresh. ULIDs from one generator are then ordered by
+    /// creation even within a clock tick. Unlike
+    /// [`UlidGenerator::next_monotonic`], this never fails: if the
+    /// increment would overflow, or the clock moves backwards, the ULID
+    /// gets a new random component. The PRNG is advanced either way.
+    ///
+    /// ```rust
+    /// use ulid::{Clock, UlidGenerator};
+    /// use xorshift::{SeedableRng, Xoroshiro128};
+    ///
+    /// struct Coarse;
+    ///
+    /// impl Clock for Coarse {
+    ///     fn now_ms(&self) -> u64 {
+    ///         1469922850259
+    ///     }
+    /// }
+    ///
+    /// let rng: Xoroshiro128 = SeedableRng::from_seed(&[1, 2][..]);
+    /// let mut ulid_gen = UlidGenerator::from_rng_and_clock(rng, Coarse);
+    /// let a = ulid_gen.ulid();
+    /// let b = ulid_gen.ulid();
+    /// assert!(a < b);
+    /// ```
+    ///
+    /// Discarding the result is almost certainly a mistake, so the
+    /// compiler warns about it:
+    ///
+    /// ```compile_fail
+    /// #![deny(unused_must_use)]
+    ///
+    /// let mut ulid_gen = ulid::UlidGenerator::new();
+    /// ulid_gen.ulid();
+    /// ```
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    #[must_use = "generating a ULID only to discard it wastes entropy"]
+    pub fn ulid(&mut self) -> Ulid {
+        let now = self.now_bits();
+        self.observe_clock(now);
+        self.stats.generated += 1;
+        let id = self.stamp_or_increment(now);
+        self.track(id);
+        id
+    }
+
+    /// Like [`UlidGenerator::ulid`], but returns an error rather than
+    /// panicking if the clock can't be read
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    pub fn try_ulid(&mut self) -> Result<Ulid, ClockError> {
+        let now = self.try_now_bits()?;
+        self.observe_clock(now);
+        self.stats.generated += 1;
+        let id = self.stamp_or_increment(now);
+        self.track(id);
+        Ok(id)
+    }
+
+    /// Generate a ULID for the current time with `random` as its random
+    /// component, bypassing the PRNG
+    ///
+    /// This suits entropy from elsewhere, such as bits derived from other
+    /// data. Only the lowest 80 bits of `random` are used, exactly as
+    /// given: the node id and sub-millisecond ordering are not applied.
+    ///
+    /// ```rust
+    /// let mut ulid_gen = ulid::UlidGenerator::new();
+    /// let id = ulid_gen.ulid_with_random(0xabcd);
+    /// assert_eq!(id.random(), 0xabcd);
+    /// ```
+    pub fn ulid_with_random(&mut self, random: u128) -> Ulid {
+        let now = self.now_bits();
+        self.observe_clock(now);
+        self.stats.generated += 1;
+
+        let id = Ulid { bits: (now.0 << 80) | (random & RANDOM_MASK) };
+        self.track(id);
+        id
+    }
+
+    /// Generate a new ULID, reporting every way that generation can fail
+    /// instead of panicking or silently truncating
+    ///
+    /// [`UlidGenerator::ulid`] panics if the clock can't be read, and
+    /// keeps only the lowest 48 bits of the timestamp. Here, both are
+    /// errors. [`UlidGenerator::next_monotonic_checked`] adds the
+    /// monotonic overflow, so a single [`GenError`] covers them all.
+    ///
+    /// ```rust
+    /// let mut ulid_gen = ulid::UlidGenerator::new();
+    /// assert!(ulid_gen.ulid_checked().is_ok());
+    /// ```
+    pub fn ulid_checked(&mut self) -> Result<Ulid, GenError> {
+        let now = self.checked_now_bits()?;
+        self.observe_clock(now);
+        self.stats.generated += 1;
+        let id = self.stamp_or_increment(now);
+        self.track(id);
+        Ok(id)
+    }
+
+    /// Generate a ULID that is strictly greater than the previous one
+    /// created by this method
+    ///
+    /// When the clock has moved on since the previous call, the ULID is
+    /// created as usual. Otherwise, the previous ULID's random component
+    /// is incremented by one, keeping its timestamp. This also protects
+    /// the sequence from the clock moving backwards.
+    ///
+    /// The first ULID in each millisecond has a uniformly random starting
+    /// point, so the chance that a burst of `n` ULIDs within the same
+    /// millisecond overflows the 80-bit random component is `n / 2^80`.
+    /// That is negligible for any realistic burst. If it does happen,
+    /// [`MonotonicOverflow`] is returned and the sequence can continue
+    /// once the clock reaches the next millisecond.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// let a = ulid_gen.next_monotonic().unwrap();
+    /// let b = ulid_gen.next_monotonic().unwrap();
+    /// assert!(a < b);
+    /// ```
+    pub fn next_monotonic(&mut self) -> Result<Ulid, MonotonicOverflow> {
+        let now = self.now_bits();
+        self.monotonic_at(now)
+    }
+
+    /// Like [`UlidGenerator::next_monotonic`], but also reports clock
+    /// failures and timestamps that don't fit in 48 bits
+    ///
+    /// See [`UlidGenerator::ulid_checked`].
+    pub fn next_monotonic_checked(&mut self) -> Result<Ulid, GenError> {
+        let now = self.checked_now_bits()?;
+        Ok(self.monotonic_at(now)?)
+    }
+
+    fn monotonic_at(&mut self, (now, fraction): (u128, u128)) -> Result<Ulid, MonotonicOverflow> {
+        self.observe_clock((now, fraction));
+        self.clock_skew = match self.previous {
+            Some(prev) if now < prev.bits >> 80 => true,
+            // the clock hasn't moved on, so it hasn't caught up yet
+            Some(prev) if now == prev.bits >> 80 => self.clock_skew,
+            _ => false,
+        };
+        let id = match self.previous {
+            Some(prev) if now <= prev.bits >> 80 => {
+                let counter = self.counter_mask();
+                if prev.bits & counter == counter || self.is_reserved(Ulid { bits: prev.bits + 1 }) {
+                    return Err(MonotonicOverflow);
+                }
+                self.stats.monotonic_increments += 1;
+                Ulid { bits: prev.bits + 1 }
+            }
+            _ => self.stamp((now, fraction)),
+        };
+
+        self.previous = Some(id);
+        self.stats.generated += 1;
+        self.track(id);
+        Ok(id)
+    }
+
+    /// Continue a monotonic sequence from `last`, such as the most recent
+    /// ULID persisted before a restart
+    ///
+    /// The next ULID from [`UlidGenerator::next_monotonic`] is strictly
+    /// greater than `last`, even if the clock is behind it. An earlier
+    /// `last` than the generator has already produced is ignored.
+    ///
+    /// ```rust
+    /// use ulid::{Ulid, UlidGenerator};
+    ///
+    /// // a ULID from the far future, ahead of the system's clock
+    /// let last = Ulid::min_for_timestamp(1 << 47);
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// ulid_gen.resume_monotonic(last);
+    /// assert!(ulid_gen.next_monotonic().unwrap() > last);
+    /// ```
+    pub fn resume_monotonic(&mut self, last: Ulid) {
+        self.previous = self.previous.max(Some(last));
+    }
+
+    /// How many more ULIDs [`UlidGenerator::next_monotonic`] can produce
+    /// before it overflows, if the clock doesn't move on
+    ///
+    /// Once the clock is in a new millisecond, the next ULID starts from
+    /// a random point, so only the upper bound is known: the size of the
+    /// counter. That is [`Ulid::max_per_millisecond`], or 2^64 with a node
+    /// id.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new_with_fixed_timestamp(1469922850259);
+    /// let before = ulid_gen.remaining_this_ms();
+    /// let _ = ulid_gen.next_monotonic().unwrap();
+    /// assert!(ulid_gen.remaining_this_ms() < before);
+    /// ```
+    pub fn remaining_this_ms(&self) -> u128 {
+        let counter = self.counter_mask();
+        match self.previous {
+            Some(prev) if self.time_bits() <= prev.bits >> 80 => counter - (prev.bits & counter),
+            _ => counter + 1,
+        }
+    }
+
+    /// Generate a monotonic ULID, waiting for the next millisecond if the
+    /// current one has run out of random values
+    ///
+    /// This behaves like [`UlidGenerator::next_monotonic`], but trades
+    /// latency for success. Waiting starts with a short spin and then
+    /// parks the thread in small increments until the clock advances.
+    ///
+    /// Returns [`MonotonicOverflow`] if the clock still hasn't moved on
+    /// after `timeout`, for example with a [`FixedClock`] or a system clock
+    /// that was set back. The generator keeps its monotonic state, so a
+    /// later call carries on from the same ULID.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// let mut ulid_gen = ulid::UlidGenerator::new();
+    /// let a = ulid_gen.next_monotonic_blocking(Duration::from_secs(1)).unwrap();
+    /// let b = ulid_gen.next_monotonic_blocking(Duration::from_secs(1)).unwrap();
+    /// assert!(a < b);
+    /// ```
+    pub fn next_monotonic_blocking(&mut self, timeout: Duration) -> Result<Ulid, MonotonicOverflow> {
+        // a timeout too long to represent never runs out
+        let deadline = std::time::Instant::now().checked_add(timeout);
+        loop {
+            match self.next_monotonic() {
+                Ok(id) => return Ok(id),
+                Err(MonotonicOverflow) => {
+                    if !self.wait_for_next_ms(deadline) {
+                        return Err(MonotonicOverflow);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wait until the clock passes the previous ULID's millisecond,
+    /// returning `false` if it hasn't done so by `deadline`
+    fn wait_for_next_ms(&self, deadline: Option<std::time::Instant>) -> bool {
+        let last = match self.previous {
+            Some(prev) => prev.bits >> 80,
+            None => return true,
+        };
+
+        let mut spins = 0;
+        while self.time_bits() <= last {
+            if spins < 100 {
+                spins += 1;
+                std::hint::spin_loop();
+            } else if !matches!(deadline, Some(deadline) if std::time::Instant::now() >= deadline) {
+                std::thread::park_timeout(Duration::from_micros(50));
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Generate a ULID for `timestamp_ms` rather than the current time
+    ///
+    /// Only the lowest 48 bits of `timestamp_ms` are used. This is useful
+    /// when importing historical data.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// let id = ulid_gen.ulid_at(1469922850259);
+    /// assert_eq!(id.timestamp_ms(), 1469922850259);
+    /// ```
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    pub fn ulid_at(&mut self, timestamp_ms: u64) -> Ulid {
+        let time_bits = timestamp_ms as u128 & ((1 << 48) - 1);
+        self.stats.generated += 1;
+        loop {
+            let id = Ulid {
+                bits: (time_bits << 80) | self.rand_bits()
+            };
+            if !self.is_reserved(id) {
+                self.track(id);
+                return id;
+            }
+        }
+    }
+
+    /// Generate `n` strictly increasing ULIDs that all share the
+    /// timestamp `ms`
+    ///
+    /// The first ULID has a random component, and each following one
+    /// increments it by one. `n` always fits within the 80-bit random
+    /// space, as `usize` is at most 64 bits wide, so the starting point
+    /// is lowered when needed to keep the sequence from overflowing.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let ids = UlidGenerator::new().ulids_for_ms(1469922850259, 3);
+    /// assert!(ids[0] < ids[1] && ids[1] < ids[2]);
+    /// ```
+    pub fn ulids_for_ms(&mut self, ms: u64, n: usize) -> Vec<Ulid> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let time_bits = ms as u128 & ((1 << 48) - 1);
+        let first = Ulid { bits: (time_bits << 80) | self.rand_bits() };
+        self.sequence_from(first, n)
+    }
+
+    /// Generate `n` strictly increasing ULIDs for the current time
+    ///
+    /// The clock is read once, and every ULID after the first increments
+    /// the random component, as [`UlidGenerator::ulids_for_ms`] does. The
+    /// result is already sorted, so it can go straight into a sorted
+    /// structure, and making it is faster than generating `n` ULIDs and
+    /// then sorting them. This doesn't affect
+    /// [`UlidGenerator::next_monotonic`].
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let ids = UlidGenerator::new().ulid_batch_sorted(1_000);
+    /// assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    /// ```
+    pub fn ulid_batch_sorted(&mut self, n: usize) -> Vec<Ulid> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let now = self.now_bits();
+        self.observe_clock(now);
+        let first = self.stamp(now);
+        self.sequence_from(first, n)
+    }
+
+    /// Generate `n` ULIDs whose timestamps are spread evenly across
+    /// `start_ms..=end_ms`, sorted in ascending order
+    ///
+    /// Each ULID gets its own random component, as
+    /// [`UlidGenerator::ulid_at`] would give it. This is meant for building
+    /// fixtures that look like data collected over a period of time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start_ms` is greater than `end_ms`.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let ids = UlidGenerator::new().batch_over_range(1_000, 2_000, 11);
+    /// assert_eq!(ids[0].timestamp_ms(), 1_000);
+    /// assert_eq!(ids[5].timestamp_ms(), 1_500);
+    /// assert_eq!(ids[10].timestamp_ms(), 2_000);
+    /// ```
+    pub fn batch_over_range(&mut self, start_ms: u64, end_ms: u64, n: usize) -> Vec<Ulid> {
+        assert!(start_ms <= end_ms, "start_ms must not be after end_ms");
+
+        let span = (end_ms - start_ms) as u128;
+        let steps = n.saturating_sub(1).max(1) as u128;
+        let mut ids: Vec<Ulid> = (0..n as u128)
+            .map(|i| {
+                let offset = span * i / steps;
+                self.ulid_at(start_ms + offset as u64) // cast is safe because offset <= span
+            })
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Turn the generator into an endless stream of monotonic ULIDs
+    ///
+    /// This is the async counterpart of
+    /// [`UlidGenerator::next_monotonic_blocking`]. When the current
+    /// millisecond has run out of random values, the stream returns
+    /// `Pending` rather than blocking the thread. A helper thread wakes the
+    /// task when the system clock reaches the next millisecond, so the
+    /// executor isn't polling it in the meantime. Only available with the
+    /// `stream` feature.
+    ///
+    /// ```rust
+    /// use futures::StreamExt;
+    ///
+    /// let stream = ulid::UlidGenerator::new().into_monotonic_stream();
+    /// let ids: Vec<_> = futures::executor::block_on(stream.take(3).collect());
+    /// assert!(ids[0] < ids[1] && ids[1] < ids[2]);
+    /// ```
+    #[cfg(feature = "stream")]
+    pub fn into_monotonic_stream(self) -> MonotonicStream<R, C> {
+        MonotonicStream { ulid_gen: self, timer: None }
+    }
+
+    /// An iterator that yields exactly `n` ULIDs from this generator
+    ///
+    /// This is like `self.by_ref().take(n)`, but its type is easy to name,
+    /// so it can be kept in a struct field. The generator carries on from
+    /// where the iterator stopped once it is dropped.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// let ids: Vec<_> = ulid_gen.bounded(3).collect();
+    /// assert_eq!(ids.len(), 3);
+    /// assert_eq!(ulid_gen.generated_count(), 3);
+    /// ```
+    pub fn bounded(&mut self, n: usize) -> Bounded<'_, R, C> {
+        Bounded { ulid_gen: self, remaining: n }
+    }
+
+    /// `n` consecutive ULIDs starting near `first`, lowering the start
+    /// when needed so that the counter bits don't overflow
+    fn sequence_from(&mut self, first: Ulid, n: usize) -> Vec<Ulid> {
+        self.stats.generated += n as u64;
+        let counter = self.counter_mask();
+        let highest_start = counter - (n as u128 - 1);
+        let start = (first.bits & !counter) | (first.bits & counter).min(highest_start);
+
+        let ids: Vec<Ulid> = (0..n as u128).map(|i| Ulid { bits: start + i }).collect();
+        if self.recent.is_some() {
+            for &id in &ids {
+                self.track(id);
+            }
+        }
+        ids
+    }
+
+    /// Returns the timestamp, in milliseconds since the UNIX epoch, that
+    /// the next ULID would carry if it were generated right now.
+    ///
+    /// The internal PRNG is not advanced.
+    #[inline]
+    pub fn peek_timestamp(&self) -> u64 {
+        self.time_bits() as u64 // cast is safe because time_bits() is masked to 48 bits
+    }
+
+    /// Move the generator on past `n` ULIDs without handing them out
+    ///
+    /// This leaves the generator as [`UlidGenerator::ulid`] would after
+    /// `n` calls that all saw the same clock reading: the PRNG, the state
+    /// used to increment ULIDs within a millisecond, and the statistics
+    /// are all updated, but the clock is read only once. The skipped ULIDs
+    /// aren't checked for duplicates. This isn't named `skip`, as that
+    /// would be shadowed by [`Iterator::skip`].
+    ///
+    /// The next ULID is therefore the one that the `n + 1`th ULID would
+    /// have been, provided that the clock doesn't move on during either
+    /// sequence. With a clock that moves on for every reading, only the
+    /// random components match.
+    ///
+    /// ```rust
+    /// use ulid::{DefaultRng, FixedClock, UlidGenerator};
+    ///
+    /// let seeded = || UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(42), FixedClock(1469922850259));
+    ///
+    /// let mut skipped = seeded();
+    /// skipped.skip_ulids(5);
+    /// assert_eq!(skipped.ulid(), seeded().nth(5).unwrap());
+    /// ```
+    pub fn skip_ulids(&mut self, n: u64) {
+        if n == 0 {
+            return;
+        }
+
+        let now = self.now_bits();
+        self.observe_clock(now);
+        self.stats.generated += n;
+        for _ in 0..n {
+            let _ = self.stamp_or_increment(now);
+        }
+    }
+
+    /// The number of ULIDs that this generator has produced
+    ///
+    /// A fresh generator starts at zero, so a count that drops between
+    /// observations means that the generator was recreated. Clones carry
+    /// the count over.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// let _ = ulid_gen.ulid();
+    /// assert_eq!(ulid_gen.generated_count(), 1);
+    /// ```
+    #[inline]
+    pub fn generated_count(&self) -> u64 {
+        self.stats.generated
+    }
+
+    /// Whether the latest call to [`UlidGenerator::next_monotonic`] found
+    /// the clock behind the previous ULID and had to keep that ULID's
+    /// timestamp to stay monotonic
+    ///
+    /// The flag is cleared by the next call that finds the clock past the
+    /// previous ULID's millisecond. A reading equal to it leaves the flag
+    /// as it was. [`GenStats::clock_backwards`] counts every backwards
+    /// step instead.
+    ///
+    /// ```rust
+    /// let mut ulid_gen = ulid::UlidGenerator::new();
+    /// let _ = ulid_gen.next_monotonic().unwrap();
+    /// assert!(!ulid_gen.clock_skew_detected());
+    /// ```
+    #[inline]
+    pub fn clock_skew_detected(&self) -> bool {
+        self.clock_skew
+    }
+
+    /// Counters describing what this generator has done so far, such as
+    /// for emitting metrics about its health
+    ///
+    /// Clock readings are only compared with each other, so ULIDs made
+    /// for an explicit timestamp, such as by [`UlidGenerator::ulid_at`],
+    /// never count as the clock going backwards.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new();
+    /// let _ = ulid_gen.next_monotonic().unwrap();
+    /// let stats = ulid_gen.stats();
+    /// assert_eq!(stats.generated, 1);
+    /// assert_eq!(stats.clock_backwards, 0);
+    /// ```
+    #[inline]
+    pub fn stats(&self) -> GenStats {
+        self.stats
+    }
+
+    /// Record a clock reading, noting whether it went backwards
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn observe_clock(&mut self, (now, _): (u128, u128)) {
+        if matches!(self.last_clock_ms, Some(last) if now < last) {
+            self.stats.clock_backwards += 1;
+        }
+        self.last_clock_ms = Some(now);
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn time_bits(&self) -> u128 {
+        self.since_epoch(self.clock.now_ms())
+    }
+
+    /// The timestamp bits for `unix_ms`, relative to the generator's epoch
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn since_epoch(&self, unix_ms: u64) -> u128 {
+        unix_ms.saturating_sub(self.epoch_ms) as u128 & ((1 << 48) - 1)
+    }
+
+    /// The timestamp and fraction bits for `unix_micros`
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn since_epoch_micros(&self, unix_micros: u64) -> (u128, u128) {
+        let fraction = (unix_micros % 1_000) as u128;
+        (self.since_epoch(unix_micros / 1_000), fraction << self.fraction_shift())
+    }
+
+    /// Count timestamps from `epoch` rather than the UNIX epoch
+    ///
+    /// **The resulting ULIDs are non-standard.** Other implementations,
+    /// along with [`Ulid::timestamp_ms`] and [`Ulid::timestamp_iso8601`],
+    /// assume the UNIX epoch and will report the wrong time. Use
+    /// [`Ulid::datetime_with_epoch`] to read the time back. A later epoch
+    /// extends the range of the 48-bit timestamp into the future, and
+    /// clock readings before `epoch` are clamped to it. Timestamps passed
+    /// explicitly, such as to [`UlidGenerator::ulid_at`], are stored as
+    /// they are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epoch` is before the UNIX epoch.
+    ///
+    /// ```rust
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    /// use ulid::UlidGenerator;
+    ///
+    /// let epoch = UNIX_EPOCH + Duration::from_secs(1_577_836_800); // 2020-01-01
+    /// let mut ulid_gen = UlidGenerator::new().with_epoch(epoch);
+    ///
+    /// let created = ulid_gen.ulid().datetime_with_epoch(epoch);
+    /// assert!(created <= SystemTime::now());
+    /// ```
+    pub fn with_epoch(mut self, epoch: SystemTime) -> Self {
+        let since_unix = epoch
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("custom epoch is before UNIX epoch");
+        self.epoch_ms = since_unix.as_millis() as u64;
+        self
+    }
+
+    /// Reserve the first 16 random bits of every generated ULID for `node`
+    ///
+    /// Generators with different node ids can never produce the same
+    /// ULID, even within the same millisecond and with identical PRNG
+    /// states. The cost is entropy: only 64 random bits remain, so
+    /// collisions between ULIDs from the *same* node become likely
+    /// after about 2^32 ULIDs in one millisecond, rather than 2^40.
+    /// Monotonic generation increments only those 64 bits.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new().with_node_id(7);
+    /// let (hi, _) = ulid_gen.ulid().split_u64s();
+    /// assert_eq!(hi & 0xffff, 7);
+    /// ```
+    pub fn with_node_id(mut self, node: u16) -> Self {
+        self.node_id = Some(node);
+        self
+    }
+
+    /// Remember the last `window` ULIDs and watch for this generator
+    /// producing any of them again
+    ///
+    /// This is a diagnostic aid for investigating reports of duplicate
+    /// ULIDs, not a guarantee of uniqueness: a repeat of a ULID older than
+    /// the window goes unnoticed. Memory use is bounded by the window,
+    /// at around 50 bytes per ULID. Check the result with
+    /// [`UlidGenerator::saw_duplicate`].
+    ///
+    /// ```rust
+    /// let mut ulid_gen = ulid::UlidGenerator::new().with_duplicate_detection(1_000);
+    /// let _: Vec<_> = ulid_gen.by_ref().take(10_000).collect();
+    /// assert!(!ulid_gen.saw_duplicate());
+    /// ```
+    pub fn with_duplicate_detection(mut self, window: usize) -> Self {
+        self.recent = Some(RecentWindow::new(window));
+        self
+    }
+
+    /// Whether this generator has produced a ULID that was still in its
+    /// recent window, see [`UlidGenerator::with_duplicate_detection`]
+    ///
+    /// Always false when duplicate detection is off.
+    pub fn saw_duplicate(&self) -> bool {
+        matches!(&self.recent, Some(recent) if recent.saw_duplicate)
+    }
+
+    /// Never produce the nil ULID or the largest possible ULID, so that
+    /// both can safely be used as sentinels
+    ///
+    /// Either would need a timestamp at one end of the 48-bit range and a
+    /// random component of all zeros or all ones, so hitting one by
+    /// chance is astronomically unlikely. This rules it out anyway: new
+    /// random bits are drawn until the ULID isn't a sentinel, and
+    /// monotonic generation reports [`MonotonicOverflow`] rather than
+    /// incrementing into the largest ULID. The batches from
+    /// [`UlidGenerator::ulids_for_ms`] and
+    /// [`UlidGenerator::ulid_batch_sorted`] are not checked. A PRNG that
+    /// only ever produces zeros never gets past a timestamp of zero.
+    ///
+    /// ```rust
+    /// use ulid::{Ulid, UlidGenerator};
+    ///
+    /// // the first ULID's random bits are all zeros
+    /// let mut values = vec![0, 0].into_iter();
+    /// let mut ulid_gen = UlidGenerator::from_closure(move || values.next().unwrap_or(1))
+    ///     .with_reserved_sentinels();
+    /// assert_ne!(ulid_gen.ulid_at(0), Ulid::new_nil());
+    /// ```
+    pub fn with_reserved_sentinels(mut self) -> Self {
+        self.reserve_sentinels = true;
+        self
+    }
+
+    /// Order ULIDs from the same millisecond by when they were created, to
+    /// the microsecond
+    ///
+    /// The microseconds within the millisecond, from 0 to 999, take up the
+    /// first 10 random bits (or the 10 bits after the node id, see
+    /// [`UlidGenerator::with_node_id`]). This leaves 70 random bits, so
+    /// collisions within the same microsecond become likely after about
+    /// 2^35 ULIDs. The clock's resolution comes from [`Clock::now_micros`].
+    /// ULIDs made for an explicit timestamp, such as by
+    /// [`UlidGenerator::ulid_at`], have no sub-millisecond time.
+    ///
+    /// ```rust
+    /// use ulid::UlidGenerator;
+    ///
+    /// let mut ulid_gen = UlidGenerator::new().with_submillis_ordering();
+    /// let micros = ulid_gen.ulid().random() >> 70;
+    /// assert!(micros < 1_000);
+    /// ```
+    pub fn with_submillis_ordering(mut self) -> Self {
+        self.submillis = true;
+        self
+    }
+
+    // The sub-millisecond fraction sits just below the node id, if any
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn fraction_shift(&self) -> u32 {
+        match self.node_id {
+            Some(_) => 54,
+            None => 70,
+        }
+    }
+
+    /// The timestamp bits and, with sub-millisecond ordering, the
+    /// fraction bits for the current time
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn now_bits(&self) -> (u128, u128) {
+        if !self.submillis {
+            return (self.time_bits(), 0);
+        }
+
+        self.since_epoch_micros(self.clock.now_micros())
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn try_now_bits(&self) -> Result<(u128, u128), ClockError> {
+        if !self.submillis {
+            return Ok((self.since_epoch(self.clock.try_now_ms()?), 0));
+        }
+
+        Ok(self.since_epoch_micros(self.clock.try_now_micros()?))
+    }
+
+    /// Like [`UlidGenerator::try_now_bits`], but rejecting timestamps
+    /// that don't fit in 48 bits rather than truncating them
+    fn checked_now_bits(&self) -> Result<(u128, u128), GenError> {
+        let (unix_ms, fraction) = if self.submillis {
+            let micros = self.clock.try_now_micros()?;
+            (micros / 1_000, ((micros % 1_000) as u128) << self.fraction_shift())
+        } else {
+            (self.clock.try_now_ms()?, 0)
+        };
+
+        let ms = unix_ms.saturating_sub(self.epoch_ms);
+        if ms >= 1 << 48 {
+            return Err(GenError::TimestampOverflow);
+        }
+        Ok((ms as u128, fraction))
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn stamp(&mut self, (time_bits, fraction): (u128, u128)) -> Ulid {
+        loop {
+            let mut bits = self.rand_bits();
+            if self.submillis {
+                bits = (bits & !(0x3ff << self.fraction_shift())) | fraction;
+            }
+
+            let id = Ulid { bits: (time_bits << 80) | bits };
+            if !self.is_reserved(id) {
+                return id;
+            }
+        }
+    }
+
+    /// Note `id` as generated, when watching for duplicates
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn track(&mut self, id: Ulid) {
+        if let Some(recent) = &mut self.recent {
+            recent.record(id);
+        }
+    }
+
+    /// Whether `id` is a sentinel that this generator must not produce
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn is_reserved(&self, id: Ulid) -> bool {
+        self.reserve_sentinels && (id.bits == 0 || id.bits == u128::MAX)
+    }
+
+    /// Like [`UlidGenerator::stamp`], but incrementing the previous ULID
+    /// instead when the clock reads the same as it did then
+    fn stamp_or_increment(&mut self, now: (u128, u128)) -> Ulid {
+        // always drawn, so that the PRNG advances by one ULID per ULID
+        let fresh = self.stamp(now);
+
+        let counter = self.counter_mask();
+        let id = match self.last_stamped {
+            Some((reading, prev))
+                if reading == now && prev.bits & counter != counter && !self.is_reserved(Ulid { bits: prev.bits + 1 }) =>
+            {
+                self.stats.monotonic_increments += 1;
+                Ulid { bits: prev.bits + 1 }
+            }
+            _ => fresh,
+        };
+
+        self.last_stamped = Some((now, id));
+        id
+    }
+
+    /// The bits that monotonic generation may increment
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn counter_mask(&self) -> u128 {
+        match self.node_id {
+            Some(_) => u64::MAX as u128,
+            None => RANDOM_MASK,
+        }
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    fn rand_bits(&mut self) -> u128 {
+        let a = self.rng.next_u64() as u128;
+        let b = self.rng.next_u64() as u128;
+
+        let mut bits  = (a << 64) | b;
+        bits &= RANDOM_MASK; // 0xfff...
+
+        if let Some(node) = self.node_id {
+            bits = ((node as u128) << 64) | (bits & u64::MAX as u128);
+        }
+
+        bits
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for DefaultRng {
+    fn zeroize(&mut self) {
+        // Xoroshiro128's layout belongs to xorshift, so replace the whole
+        // value through its public API rather than writing over its bytes
+        self.0 = Xoroshiro128::from_seed(&[0u64; 2][..]);
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for DefaultRng {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for DefaultRng {}
+
+#[cfg(feature = "zeroize")]
+impl<R: zeroize::Zeroize, C> zeroize::Zeroize for UlidGenerator<R, C> {
+    fn zeroize(&mut self) {
+        self.rng.zeroize();
+    }
+}
+
+// The PRNG wipes itself when dropped
+#[cfg(feature = "zeroize")]
+impl<R: zeroize::ZeroizeOnDrop, C> zeroize::ZeroizeOnDrop for UlidGenerator<R, C> {}
+
+impl Default for UlidGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The PRNG's state is left out of both Debug and Display. Printing it
+// would let anyone reading the logs predict future ULIDs.
+
+impl<R, C> fmt::Debug for UlidGenerator<R, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UlidGenerator")
+            .field("submillis", &self.submillis)
+            .field("node_id", &self.node_id)
+            .field("epoch_ms", &self.epoch_ms)
+            .field("reserve_sentinels", &self.reserve_sentinels)
+            .field("duplicate_window", &self.recent.as_ref().map(|recent| recent.size))
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Summarizes the generator's configuration, for example for logs
+///
+/// ```rust
+/// let ulid_gen = ulid::UlidGenerator::new().with_node_id(7);
+/// assert_eq!(
+///     ulid_gen.to_string(),
+///     "UlidGenerator (ordering: random, node id: 7, epoch: UNIX, sentinels: allowed, \
+///      duplicate window: none, generated: 0)"
+/// );
+/// ```
+impl<R, C> Display for UlidGenerator<R, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let ordering = if self.submillis { "sub-millisecond" } else { "random" };
+        write!(f, "UlidGenerator (ordering: {}, ", ordering)?;
+
+        match self.node_id {
+            Some(node) => write!(f, "node id: {}, ", node)?,
+            None => write!(f, "node id: none, ")?,
+        }
+
+        match self.epoch_ms {
+            0 => write!(f, "epoch: UNIX, ")?,
+            ms => write!(f, "epoch: UNIX + {} ms, ", ms)?,
+        }
+
+        let sentinels = if self.reserve_sentinels { "reserved" } else { "allowed" };
+        write!(f, "sentinels: {}, ", sentinels)?;
+
+        match &self.recent {
+            Some(recent) => write!(f, "duplicate window: {}, ", recent.size)?,
+            None => write!(f, "duplicate window: none, ")?,
+        }
+
+        write!(f, "generated: {})", self.stats.generated)
+    }
+}
+
+impl<R: Rng, C: Clock> Iterator for UlidGenerator<R, C> {
+    type Item = Ulid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.ulid())
+    }
+
+    // The sequence never ends
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<R: Rng, C: Clock> FusedIterator for UlidGenerator<R, C> {}
+
+/// A stream of monotonic ULIDs that waits for the next millisecond
+/// without blocking the executor
+///
+/// See [`UlidGenerator::into_monotonic_stream`]. Only available with the
+/// `stream` feature.
+#[cfg(feature = "stream")]
+#[derive(Debug)]
+pub struct MonotonicStream<R = DefaultRng, C = SystemClock> {
+    ulid_gen: UlidGenerator<R, C>,
+    // the waker a timer thread will wake once the millisecond is over, or
+    // `None` once it has fired
+    timer: Option<std::sync::Arc<std::sync::Mutex<Option<core::task::Waker>>>>,
+}
+
+#[cfg(feature = "stream")]
+impl<R, C> MonotonicStream<R, C> {
+    /// Recover the generator, keeping its monotonic state
+    pub fn into_inner(self) -> UlidGenerator<R, C> {
+        self.ulid_gen
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<R: Rng + Unpin, C: Clock + Unpin> futures_core::Stream for MonotonicStream<R, C> {
+    type Item = Ulid;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Ulid>> {
+        let this = self.get_mut();
+        match this.ulid_gen.next_monotonic() {
+            Ok(id) => core::task::Poll::Ready(Some(id)),
+            Err(MonotonicOverflow) => {
+                if let Some(slot) = &this.timer {
+                    let mut waker = slot.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                    if waker.is_some() {
+                        // a timer is already running, make sure it wakes this task
+                        *waker = Some(cx.waker().clone());
+                        return core::task::Poll::Pending;
+                    }
+                }
+                this.timer = Some(wake_next_millisecond(cx.waker().clone()));
+                core::task::Poll::Pending
+            }
+        }
+    }
+
+    // The stream never ends
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Wake `waker` from another thread once the system clock has moved on to
+/// the next millisecond
+#[cfg(feature = "stream")]
+fn wake_next_millisecond(
+    waker: core::task::Waker,
+) -> std::sync::Arc<std::sync::Mutex<Option<core::task::Waker>>> {
+    let slot = std::sync::Arc::new(std::sync::Mutex::new(Some(waker)));
+    let timer_slot = std::sync::Arc::clone(&slot);
+    std::thread::spawn(move || {
+        let into_ms = try_duration_since_epoch().unwrap_or_default().subsec_nanos() % 1_000_000;
+        std::thread::sleep(Duration::from_nanos(u64::from(1_000_000 - into_ms)));
+
+        let waker = timer_slot.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    slot
+}
+
+/// An iterator over a fixed number of ULIDs, borrowing its generator
+///
+/// See [`UlidGenerator::bounded`].
+#[derive(Debug)]
+pub struct Bounded<'a, R = DefaultRng, C = SystemClock> {
+    ulid_gen: &'a mut UlidGenerator<R, C>,
+    remaining: usize,
+}
+
+impl<'a, R, C> Bounded<'a, R, C> {
+    /// The number of ULIDs left to yield
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, R: Rng, C: Clock> Iterator for Bounded<'a, R, C> {
+    type Item = Ulid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        Some(self.ulid_gen.ulid())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, R: Rng, C: Clock> ExactSizeIterator for Bounded<'a, R, C> {}
+
+impl<'a, R: Rng, C: Clock> FusedIterator for Bounded<'a, R, C> {}
+
+/// An iterator that merges several sorted sources of ULIDs into one
+/// sorted sequence
+///
+/// See [`merge_sorted`].
+#[derive(Debug)]
+pub struct MergeSorted<I: Iterator<Item = Ulid>> {
+    sources: Vec<Peekable<I>>,
+}
+
+impl<I: Iterator<Item = Ulid>> Iterator for MergeSorted<I> {
+    type Item = Ulid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut smallest: Option<(usize, Ulid)> = None;
+        for (i, source) in self.sources.iter_mut().enumerate() {
+            if let Some(&id) = source.peek() {
+                // ties go to the earlier source
+                if !matches!(smallest, Some((_, min)) if min <= id) {
+                    smallest = Some((i, id));
+                }
+            }
+        }
+
+        let (i, _) = smallest?;
+        self.sources[i].next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources.iter().fold((0, Some(0)), |(lo, hi), source| {
+            let (source_lo, source_hi) = source.size_hint();
+            let hi = match (hi, source_hi) {
+                (Some(a), Some(b)) => a.checked_add(b),
+                _ => None,
+            };
+            (lo.saturating_add(source_lo), hi)
+        })
+    }
+}
+
+/// Create a unique ULID as a base32-encoded string
+///
+/// # Examples
+///
+/// ```rust
+/// use ulid::ulid;
+///
+/// let a = ulid();
+/// let b = ulid();
+/// assert_ne!(a, b);
+/// ```
+///
+/// Discarding the result triggers a warning:
+///
+/// ```compile_fail
+/// #![deny(unused_must_use)]
+///
+/// ulid::ulid();
+/// ```
+#[must_use]
+pub fn ulid() -> String {
+    UlidGenerator::new().ulid().to_string()
+}
+
+/// Create a unique ULID as a `u128`
+///
+/// Discarding the result triggers a warning:
+///
+/// ```compile_fail
+/// #![deny(unused_must_use)]
+///
+/// ulid::ulid_raw();
+/// ```
+#[must_use]
+pub fn ulid_raw() -> u128 {
+    UlidGenerator::new().ulid().bits
+}
+
+/// Apply Crockford's substitutions for commonly confused characters
+///
+/// Lowercase letters are uppercased, `O` is read as `0`, and `I` and
+/// `L` are read as `1`. This is intended to be used on user-entered
+/// text before decoding it. Characters that are not part of the
+/// alphabet are left untouched.
+///
+/// # Examples
+///
+/// ```rust
+/// use ulid::normalize_crockford;
+///
+/// let typed = "01arz3ndektsv4rrffq69g5fav";
+/// assert_eq!(normalize_crockford(typed), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+///
+/// let misread = "OlARZ3NDEKTSV4RRFFQ69G5FAV";
+/// assert_eq!(normalize_crockford(misread), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+/// ```
+pub fn normalize_crockford(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii() {
+                base32::normalize(c as u8) as char
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Compare two canonical ULID strings by the values they encode
+///
+/// Crockford's Base32 alphabet is in ascending ASCII order, and ULIDs are
+/// fixed-width, so comparing the bytes of two canonical (uppercase) ULID
+/// strings gives the same result as decoding and comparing the `Ulid`s.
+/// This avoids decoding entirely.
+///
+/// Both strings are checked first. Lowercase letters would compare above
+/// every uppercase one, so they are reported as
+/// [`ParseError::InvalidChar`] rather than giving a wrong answer. Use
+/// [`Ulid::cmp_str`] when either side may be lowercase.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cmp::Ordering;
+///
+/// let a = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+/// let b = "01BX5ZZKBKACTAV9WEVGEMMVRZ";
+/// assert_eq!(ulid::str_cmp(a, b), Ok(Ordering::Less));
+/// assert!(ulid::str_cmp(a, "01bx5zzkbkactav9wevgemmvrz").is_err());
+/// ```
+pub fn str_cmp(a: &str, b: &str) -> Result<Ordering, ParseError> {
+    check_canonical(a)?;
+    check_canonical(b)?;
+    Ok(a.as_bytes().cmp(b.as_bytes()))
+}
+
+/// Check that `s` is a ULID in canonical (uppercase) form
+fn check_canonical(s: &str) -> Result<(), ParseError> {
+    if s.len() != ULID_LEN {
+        return Err(ParseError::InvalidLength { len: s.len() });
+    }
+
+    for (i, b) in s.bytes().enumerate() {
+        if b.is_ascii_lowercase() || base32::reverse_lookup(b).is_none() {
+            return Err(ParseError::InvalidChar { position: i, byte: b });
+        }
+    }
+    if s.as_bytes()[0] > b'7' {
+        return Err(ParseError::Overflow);
+    }
+
+    Ok(())
+}
+
+/// The ULID string immediately after `s`, in value order
+///
+/// This is handy for building exclusive upper bounds for range scans
+/// over string keys: `[start, successor_str(end)?)` includes `end`.
+/// The output is always canonical (uppercase).
+///
+/// Returns [`ParseError::Overflow`] when `s` is the largest possible
+/// ULID, `7ZZZZZZZZZZZZZZZZZZZZZZZZZ`.
+///
+/// # Examples
+///
+/// ```rust
+/// let next = ulid::successor_str("01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap();
+/// assert_eq!(next, "01ARZ3NDEKTSV4RRFFQ69G5FAW");
+/// ```
+pub fn successor_str(s: &str) -> Result<String, ParseError> {
+    let id: Ulid = s.parse()?;
+    let bits = id.bits.checked_add(1).ok_or(ParseError::Overflow)?;
+    Ok(Ulid { bits }.to_string())
+}
+
+/// Merge sorted sources of ULIDs, such as one generator per shard, into
+/// a single sorted iterator
+///
+/// Each step peeks at every source and yields the smallest ULID. The
+/// output is only sorted if every source is, which holds for a generator
+/// whose clock only moves forward, or for ULIDs drawn from
+/// [`UlidGenerator::next_monotonic`].
+///
+/// # Examples
+///
+/// ```rust
+/// use ulid::UlidGenerator;
+///
+/// let shards = vec![UlidGenerator::deterministic(), UlidGenerator::deterministic()];
+/// let ids: Vec<_> = ulid::merge_sorted(shards).take(100).collect();
+/// assert!(ids.windows(2).all(|pair| pair[0] <= pair[1]));
+/// ```
+pub fn merge_sorted<I>(sources: impl IntoIterator<Item = I>) -> MergeSorted<I::IntoIter>
+where
+    I: IntoIterator<Item = Ulid>,
+{
+    MergeSorted {
+        sources: sources.into_iter().map(|source| source.into_iter().peekable()).collect(),
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_support {
+    use super::{Ulid, UlidComponents, ULID_LEN};
+    use core::fmt;
+    use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+    use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+    impl Serialize for Ulid {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut buf = [0; super::ULID_LEN];
+            super::base32::encode(self.bits, &mut buf);
+            // Safety: guaranteed to be UTF-8 because we control the
+            //         bytes that are present.
+            let ascii_ulid = unsafe { core::str::from_utf8_unchecked(&buf) };
+            serializer.serialize_str(ascii_ulid)
+        }
+    }
+
+    pub(crate) struct UlidVisitor;
+
+    impl<'de> Visitor<'de> for UlidVisitor {
+        type Value = Ulid;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a 26 character ULID string")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Ulid, E> {
+            v.parse().map_err(E::custom)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Ulid {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(UlidVisitor)
+        }
+    }
+
+    // The random component occupies the last 16 characters of a ULID
+    const RANDOM_START: usize = ULID_LEN - 16;
+    const FIELDS: &[&str] = &["timestamp_ms", "random"];
+
+    impl Serialize for UlidComponents {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut buf = [0; ULID_LEN];
+            super::base32::encode(self.random & super::RANDOM_MASK, &mut buf);
+            // Safety: guaranteed to be UTF-8 because we control the
+            //         bytes that are present.
+            let random = unsafe { core::str::from_utf8_unchecked(&buf[RANDOM_START..]) };
+
+            let mut out = serializer.serialize_struct("UlidComponents", 2)?;
+            out.serialize_field("timestamp_ms", &self.timestamp_ms)?;
+            out.serialize_field("random", random)?;
+            out.end()
+        }
+    }
+
+    fn decode_random<E: de::Error>(random: &str) -> Result<u128, E> {
+        if random.len() != ULID_LEN - RANDOM_START {
+            return Err(E::invalid_length(random.len(), &"16 characters"));
+        }
+
+        let mut buf = [b'0'; ULID_LEN];
+        buf[RANDOM_START..].copy_from_slice(random.as_bytes());
+        super::base32::decode(&buf).map_err(E::custom)
+    }
+
+    struct ComponentsVisitor;
+
+    impl<'de> Visitor<'de> for ComponentsVisitor {
+        type Value = UlidComponents;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a struct with timestamp_ms and random fields")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let timestamp_ms = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let random: String = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+            Ok(UlidComponents {
+                timestamp_ms,
+                random: decode_random(&random)?,
+            })
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut timestamp_ms = None;
+            let mut random = None;
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "timestamp_ms" => timestamp_ms = Some(map.next_value()?),
+                    "random" => random = Some(decode_random(&map.next_value::<String>()?)?),
+                    other => return Err(de::Error::unknown_field(other, FIELDS)),
+                }
+            }
+
+            Ok(UlidComponents {
+                timestamp_ms: timestamp_ms.ok_or_else(|| de::Error::missing_field("timestamp_ms"))?,
+                random: random.ok_or_else(|| de::Error::missing_field("random"))?,
+            })
+        }
+    }
+
+    impl<'de> Deserialize<'de> for UlidComponents {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_struct("UlidComponents", FIELDS, ComponentsVisitor)
+        }
+    }
+}
+
+/// Serialize maps keyed by `Ulid` with the keys as strings
+///
+/// Many formats, such as JSON, require map keys to be strings. Use this
+/// module with `#[serde(with = "ulid::serde_str_key")]` on a `HashMap<Ulid, V>`
+/// field to guarantee that its keys are written in the canonical text form,
+/// whatever the format.
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use serde::{Deserialize, Serialize};
+/// use ulid::Ulid;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Scores {
+///     #[serde(with = "ulid::serde_str_key")]
+///     by_id: HashMap<Ulid, i32>,
+/// }
+/// ```
+#[cfg(feature = "serde")]
+pub mod serde_str_key {
+    use super::Ulid;
+    use core::fmt;
+    use core::hash::BuildHasher;
+    use core::marker::PhantomData;
+    use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+    use serde::ser::{SerializeMap, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    struct Key;
+
+    impl<'de> serde::de::DeserializeSeed<'de> for Key {
+        type Value = Ulid;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Ulid, D::Error> {
+            deserializer.deserialize_str(super::serde_support::UlidVisitor)
+        }
+    }
+
+    pub fn serialize<V, H, S>(map: &HashMap<Ulid, V, H>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        V: Serialize,
+        S: Serializer,
+    {
+        let mut out = serializer.serialize_map(Some(map.len()))?;
+        for (k, v) in map {
+            out.serialize_entry(&k.to_string(), v)?;
+        }
+        out.end()
+    }
+
+    pub fn deserialize<'de, V, H, D>(deserializer: D) -> Result<HashMap<Ulid, V, H>, D::Error>
+    where
+        V: Deserialize<'de>,
+        H: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        struct MapVisitor<V, H>(PhantomData<(V, H)>);
+
+        impl<'de, V, H> Visitor<'de> for MapVisitor<V, H>
+        where
+            V: Deserialize<'de>,
+            H: BuildHasher + Default,
+        {
+            type Value = HashMap<Ulid, V, H>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map with ULID string keys")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut map = HashMap::with_capacity_and_hasher(
+                    access.size_hint().unwrap_or(0),
+                    H::default(),
+                );
+                while let Some((k, v)) = access.next_entry_seed(Key, PhantomData)? {
+                    map.insert(k, v);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+/// Serialize a `Ulid` as its 16 big-endian bytes
+///
+/// By default, a `Ulid` is serialized as a string. Binary formats, such as
+/// MessagePack, store a byte string more compactly. Use this module with
+/// `#[serde(with = "ulid::serde_binary")]` on a `Ulid` field. The bytes are
+/// passed to the format as a byte string rather than a sequence, so
+/// MessagePack emits a single `bin` value.
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use ulid::Ulid;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Event {
+///     #[serde(with = "ulid::serde_binary")]
+///     id: Ulid,
+/// }
+/// ```
+#[cfg(feature = "serde")]
+pub mod serde_binary {
+    use super::{Ulid, ULID_BINARY_LEN};
+    use core::fmt;
+    use serde::de::{self, Deserializer, SeqAccess, Visitor};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S: Serializer>(id: &Ulid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&id.to_be_bytes())
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Ulid;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} bytes", ULID_BINARY_LEN)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Ulid, E> {
+            if v.len() != ULID_BINARY_LEN {
+                return Err(E::invalid_length(v.len(), &self));
+            }
+            let mut bytes = [0; ULID_BINARY_LEN];
+            bytes.copy_from_slice(v);
+            Ok(Ulid::from_be_bytes(bytes))
+        }
+
+        // Some formats, such as JSON, have no byte strings and fall back
+        // to a sequence of integers
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Ulid, A::Error> {
+            let mut bytes = [0; ULID_BINARY_LEN];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            }
+            if seq.next_element::<u8>()?.is_some() {
+                return Err(de::Error::invalid_length(ULID_BINARY_LEN + 1, &self));
+            }
+            Ok(Ulid::from_be_bytes(bytes))
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Ulid, D::Error> {
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// Serialize a `Ulid` as a CBOR tagged value wrapping its 16 big-endian
+/// bytes
+///
+/// The tag lets other CBOR consumers recognize the value as a ULID rather
+/// than arbitrary bytes. Use this module with
+/// `#[serde(with = "ulid::cbor_tagged")]` on a `Ulid` field, with
+/// [`ciborium`] as the format. Deserializing requires the tag. Only
+/// available with the `cbor` feature.
+#[cfg(feature = "cbor")]
+pub mod cbor_tagged {
+    use super::{serde_binary, Ulid};
+    use ciborium::tag::Required;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// The CBOR tag number, the ASCII bytes of `ulid`
+    ///
+    /// It is in the first-come, first-served range of the IANA registry
+    /// but is not registered.
+    pub const TAG: u64 = 0x756c_6964;
+
+    struct Binary(Ulid);
+
+    impl Serialize for Binary {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serde_binary::serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Binary {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            serde_binary::deserialize(deserializer).map(Binary)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(id: &Ulid, serializer: S) -> Result<S::Ok, S::Error> {
+        Required::<_, TAG>(Binary(*id)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Ulid, D::Error> {
+        let Required(Binary(id)) = Required::<Binary, TAG>::deserialize(deserializer)?;
+        Ok(id)
+    }
+}
+
+#[cfg(feature = "ffi")]
+mod ffi {
+    use super::*;
+    use ::libc::{c_char, c_int, size_t, ERANGE};
+    use std::slice::from_raw_parts_mut;
+
+    /// Error code for when the system clock can't be read, typically because
+    /// it is set to before the UNIX epoch. Functions return its negation.
+    pub const ULID_ECLOCK: c_int = 1001;
+
+    /// Binary ULID, most significant byte first
+    ///
+    /// Comparing two `ulid` values byte by byte orders them the same
+    /// way as their text form.
+    #[allow(non_camel_case_types)]
+    pub type ulid = [u8; ULID_BINARY_LEN];
+
+    impl From<Ulid> for ulid {
+        #[inline]
+        fn from(id: Ulid) -> Self {
+            id.to_be_bytes()
+        }
+    }
+
+    impl From<ulid> for Ulid {
+        #[inline]
+        fn from(id: ulid) -> Self {
+            Ulid::from_be_bytes(id)
+        }
+    }
+
+    /// Context object for `ulid` operations
+    ///
+    /// Contains information related to the internal RNG.
+    // #[repr(rust)] so that cbindgen generates an opaque struct
+    #[allow(non_camel_case_types)]
+    pub struct ulid_ctx {
+        pub(crate) gen: UlidGenerator,
+    }
+
+    impl ulid_ctx {
+        /// Returns null if a new context is needed but the clock can't be read
+        #[inline]
+        #[must_use]
+        unsafe fn ensure_init(ctx: *mut ulid_ctx) -> *mut ulid_ctx {
+            if ctx.is_null() {
+                ulid_init(0)
+            } else {
+                ctx
+            }
+        }
+    }
+
+    /// Destroy the `ulid_ctx` object
+    ///
+    /// # Safety
+    ///
+    /// Must not be called on the same value twice. This results
+    /// in a double free.
+    #[no_mangle]
+    pub unsafe extern "C" fn ulid_ctx_destroy(ctx: *mut ulid_ctx) {
+        drop(Box::from_raw(ctx));
+    }
+
+    /// Generate a `ulid_ctx` and seed the random number generator (RNG)
+    /// provided by your system's libc implementation of the rand() family.
+    ///
+    /// Passing 0 as `seed` will seed the random number generator from the
+    /// system's clock.
+    ///
+    /// Returns a null pointer if the system's clock can't be read.
+    #[no_mangle]
+    pub extern "C" fn ulid_init(seed: u32) -> *mut ulid_ctx {
+        let gen = match seed {
+            0 => match super::UlidGenerator::try_new() {
+                Ok(gen) => gen,
+                Err(ClockError) => return std::ptr::null_mut(),
+            },
+            s => {
+                super::UlidGenerator::from_seed(s as u64)
+            }
+        };
+
+        let ctx = ulid_ctx { gen };
+        Box::leak(Box::new(ctx))
+    }
+
+    // /// Seed the random number generator with `s`
+    // #[no_mangle]
+    // pub unsafe extern "C" fn ulid_seed(s: u32) {
+    //     seed(s);
+    // }
+
+    /// Create a new 128-bit ULID in `dest`.
+    ///
+    /// If the `ctx` pointer is null, the random number generator is re-seeded
+    /// from the system's clock.
+    ///
+    /// The destination `dest` must be a valid, non-null, pointer to `ulid`.
+    ///
+    /// Returns 0 on success, or a negative error code on failure.
+    #[no_mangle]
+    pub unsafe extern "C" fn ulid_new(ctx: *mut ulid_ctx, dest: &mut ulid) -> c_int {
+        let ctx = ulid_ctx::ensure_init(ctx);
+        if ctx.is_null() {
+            return -ULID_ECLOCK;
+        }
+
+        new_into(&mut (*ctx).gen, dest)
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    pub(crate) fn new_into<R: Rng, C: Clock>(gen: &mut UlidGenerator<R, C>, dest: &mut ulid) -> c_int {
+        match gen.try_ulid() {
+            Ok(id) => {
+                *dest = id.into();
+                0
+            }
+            Err(ClockError) => -ULID_ECLOCK,
+        }
+    }
+
+    /// Create a new 128-bit ULID in `dest` for the time `timestamp_ms`,
+    /// expressed in milliseconds since the UNIX epoch.
+    ///
+    /// Only the lowest 48 bits of `timestamp_ms` are used.
+    ///
+    /// If the `ctx` pointer is null, the random number generator is re-seeded
+    /// from the system's clock. Otherwise, the system's clock is never read,
+    /// so together with a `ctx` from `ulid_init` with a non-zero `seed`, this
+    /// suits targets without a usable clock.
+    ///
+    /// The destination `dest` must be a valid, non-null, pointer to `ulid`.
+    ///
+    /// Returns 0 on success, or a negative error code on failure.
+    #[no_mangle]
+    pub unsafe extern "C" fn ulid_new_at(
+        ctx: *mut ulid_ctx,
+        timestamp_ms: u64,
+        dest: &mut ulid,
+    ) -> c_int {
+        let ctx = ulid_ctx::ensure_init(ctx);
+        if ctx.is_null() {
+            return -ULID_ECLOCK;
+        }
+
+        *dest = (*ctx).gen.ulid_at(timestamp_ms).into();
+        0
+    }
+
+    /// Write a new ULID to `dest` as a string.
+    ///
+    /// Crockford's Base32 alphabet is used, and exactly 27 bytes are written,
+    /// including the terminating null byte.
+    ///
+    /// The destination `dest` must be a valid, non-null, pointer to a `char`
+    /// buffer with `size` bytes, and should have at least 27 bytes.
+    ///
+    /// If the `ctx` pointer is null, the random number generator is re-seeded
+    /// from the system's clock.
     ///
     /// Returns the number of characters printed (excluding the terminating null
     /// byte) on success, or a negative error code on failure.
@@ -334,68 +4433,2567 @@ mod ffi {
             return -ERANGE;
         }
 
-        let ctx = ulid_ctx::ensure_init(ctx);
+        let ctx = ulid_ctx::ensure_init(ctx);
+        if ctx.is_null() {
+            return -ULID_ECLOCK;
+        }
+
+        write_new_into(&mut (*ctx).gen, from_raw_parts_mut(dest as *mut u8, size))
+    }
+
+    #[cfg_attr(not(feature = "profiling"), inline)]
+    pub(crate) fn write_new_into<R: Rng, C: Clock>(gen: &mut UlidGenerator<R, C>, dest: &mut [u8]) -> c_int {
+        let id = match gen.try_ulid() {
+            Ok(id) => id,
+            Err(ClockError) => return -ULID_ECLOCK,
+        };
+
+        base32::encode(id.bits, dest);
+        dest[ULID_LEN] = 0;
+
+        ULID_LEN as c_int // cast is safe because ULID_LEN is tiny
+    }
+
+    /// Write the 128-bit ULID pointed by `id` to `dest` as a string.
+    ///
+    /// Crockford's Base32 alphabet is used, and exactly 27 bytes are written,
+    /// including the terminating null byte.
+    ///
+    /// The destination `dest` must be a valid, non-null, pointer to a `char`
+    /// buffer with `size` bytes, and should have at least 27 bytes.
+    ///
+    /// Returns the number of characters printed (excluding the terminating null
+    /// byte) on success, or a negative error code on failure.
+    #[no_mangle]
+    pub unsafe extern "C" fn ulid_write(id: &ulid, dest: *mut c_char, size: size_t) -> c_int {
+        if size < ULID_LEN + 1 {
+            return -ERANGE;
+        }
+
+        let slice = from_raw_parts_mut(dest as *mut u8, size);
+        base32::encode(u128::from_be_bytes(*id), slice);
+        slice[ULID_LEN] = 0;
+
+        ULID_LEN as c_int // cast is safe because ULID_LEN is tiny
+    }
+}
+
+#[cfg(test)]
+mod that {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // too slow and unlikely to pass in Miri
+    fn each_ulid_is_unique() {
+        use itertools::Itertools;
+
+        let ulids: Vec<_> = (0..100).map(|_| ulid()).collect();
+        println!("{:?}", ulids);
+        for pair in ulids.into_iter().permutations(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn newer_ulids_are_higher() {
+        use std::thread::sleep;
+        use std::time::Duration;
+
+        let a = ulid();
+        sleep(Duration::from_millis(2));
+        let b = ulid();
+        assert!(a < b);
+    }
+
+    const CANONICAL: &str = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+
+    /// Always produces the same value
+    struct RiggedRng(u64);
+
+    impl Rng for RiggedRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0 as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0
+        }
+    }
+
+    fn frozen_generator(now_ms: u64) -> UlidGenerator<Xoroshiro128, FixedClock> {
+        let rng: Xoroshiro128 = SeedableRng::from_seed(&[1, 2][..]);
+        UlidGenerator::from_rng_and_clock(rng, FixedClock(now_ms))
+    }
+
+    #[test]
+    fn parsing_reverses_display() {
+        for _ in 0..100 {
+            let id = Ulid::new();
+            assert_eq!(id.to_string().parse::<Ulid>(), Ok(id));
+        }
+    }
+
+    #[test]
+    fn parsing_rejects_bad_input() {
+        assert_eq!("".parse::<Ulid>(), Err(ParseError::InvalidLength { len: 0 }));
+        assert_eq!("01ARZ3NDEKTSV4RRFFQ69G5FA".parse::<Ulid>(), Err(ParseError::InvalidLength { len: 25 }));
+        assert_eq!("01ARZ3NDEKTSV4RRFFQ69G5FAU".parse::<Ulid>(), Err(ParseError::InvalidChar { position: 25, byte: b'U' }));
+        assert_eq!("81ARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Ulid>(), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn decoding_rejects_values_in_the_overflow_bits() {
+        assert_eq!("ZZZZZZZZZZZZZZZZZZZZZZZZZZ".parse::<Ulid>(), Err(ParseError::Overflow));
+        assert_eq!("Z0000000000000000000000000".parse::<Ulid>(), Err(ParseError::Overflow));
+        assert_eq!("80000000000000000000000000".parse::<Ulid>(), Err(ParseError::Overflow));
+        assert_eq!(UlidParser::new().parse("z0000000000000000000000000"), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn encoding_and_decoding_are_exact_inverses_at_the_edges_of_the_valid_space() {
+        let max = Ulid::from_u64s(u64::MAX, u64::MAX);
+        assert_eq!(max.to_string(), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+        assert_eq!("7ZZZZZZZZZZZZZZZZZZZZZZZZZ".parse::<Ulid>(), Ok(max));
+
+        let nil = Ulid::new_nil();
+        assert_eq!(nil.to_string(), "00000000000000000000000000");
+        assert_eq!("00000000000000000000000000".parse::<Ulid>(), Ok(nil));
+
+        // each of the 128 bits on its own
+        for shift in 0..128 {
+            let id = Ulid { bits: 1 << shift };
+            assert_eq!(id.to_string().parse::<Ulid>(), Ok(id));
+        }
+    }
+
+    #[test]
+    fn parse_errors_describe_what_went_wrong() {
+        let cases = [
+            (ParseError::InvalidLength { len: 25 }, "invalid length: found 25 bytes"),
+            (
+                ParseError::InvalidChar { position: 25, byte: b'U' },
+                "invalid character 'U' at position 25",
+            ),
+            (
+                ParseError::InvalidChar { position: 3, byte: 0xc3 },
+                "invalid byte 0xc3 at position 3",
+            ),
+            (ParseError::Overflow, "value exceeds 128 bits (the first character must be between 0 and 7)"),
+            (ParseError::ChecksumMismatch, "check symbol does not match the ULID"),
+        ];
+
+        for (err, msg) in cases.iter() {
+            assert_eq!(err.to_string(), *msg);
+        }
+    }
+
+    #[test]
+    fn parse_error_messages_point_at_the_offending_character() {
+        let err = "01ARZINDEKTSV4RRFFQ69G5FAV".parse::<Ulid>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid character 'I' at position 5");
+
+        let err = Ulid::parse_trimmed("  01ARZ3NDEKTSV4RRFFQ69G5F-V ").unwrap_err();
+        assert_eq!(err.to_string(), "invalid character '-' at position 24");
+
+        let err = "01ARZ3NDEKTSV4RRFFQ69G5F\u{e9}".parse::<Ulid>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid byte 0xc3 at position 24");
+    }
+
+    #[test]
+    fn other_errors_describe_what_went_wrong() {
+        assert!(MonotonicOverflow.to_string().contains("same millisecond"));
+        assert!(ClockError.to_string().contains("UNIX epoch"));
+        assert!(TimestampOutOfRange.to_string().contains("maximum"));
+    }
+
+    #[test]
+    fn errors_work_as_trait_objects() {
+        let err: Box<dyn std::error::Error> = "01ARZ3NDEKTSV4RRFFQ69G5FAU".parse::<Ulid>().unwrap_err().into();
+        assert_eq!(err.to_string(), "invalid character 'U' at position 25");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn invalid_characters_are_reported_as_written() {
+        let parser = UlidParser::new().normalize_confusables(true);
+        assert_eq!(
+            parser.parse("01ARZ3NDEKTSV4RRFFQ69G5FAu"),
+            Err(ParseError::InvalidChar { position: 25, byte: b'u' })
+        );
+
+        assert_eq!(
+            Ulid::parse_grouped("01ARZ3ND EKTSV4RR FFQ69G5F é", ' '),
+            Err(ParseError::InvalidChar { position: 24, byte: 0xc3 })
+        );
+    }
+
+    #[test]
+    fn decoding_handles_every_byte_value() {
+        // Runs under Miri to confirm that invalid input can't reach UB
+        const ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZabcdefghjkmnpqrstvwxyz";
+
+        for &pos in &[0, 13, ULID_LEN - 1] {
+            for b in 0..=255u8 {
+                let mut input = *b"01ARZ3NDEKTSV4RRFFQ69G5FAV";
+                input[pos] = b;
+
+                let result = base32::decode(&input);
+                if !ALPHABET.contains(&b) {
+                    assert_eq!(result, Err(ParseError::InvalidChar { position: pos, byte: b }));
+                } else if pos == 0 && !b"01234567".contains(&b) {
+                    assert_eq!(result, Err(ParseError::Overflow));
+                } else {
+                    assert!(result.is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parsing_is_strict_about_whitespace() {
+        assert_eq!(" 01ARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Ulid>(), Err(ParseError::InvalidLength { len: 27 }));
+    }
+
+    #[test]
+    fn parsing_trimmed_ignores_leading_whitespace() {
+        let id = Ulid::parse_trimmed("  \t01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap();
+        assert_eq!(id.to_string(), CANONICAL);
+    }
+
+    #[test]
+    fn parsing_trimmed_ignores_trailing_whitespace() {
+        let id = Ulid::parse_trimmed("01ARZ3NDEKTSV4RRFFQ69G5FAV\r\n").unwrap();
+        assert_eq!(id.to_string(), CANONICAL);
+    }
+
+    #[test]
+    fn parsing_trimmed_ignores_surrounding_whitespace() {
+        let id = Ulid::parse_trimmed("  01ARZ3NDEKTSV4RRFFQ69G5FAV\n").unwrap();
+        assert_eq!(id.to_string(), CANONICAL);
+    }
+
+    #[test]
+    fn parsing_trimmed_rejects_internal_whitespace() {
+        assert!(Ulid::parse_trimmed("01ARZ3NDEKTSV 4RRFFQ69G5FAV").is_err());
+        assert!(Ulid::parse_trimmed(" 01ARZ3NDEK TSV4RRFFQ69G5FA ").is_err());
+    }
+
+    #[test]
+    fn decoding_bytes_agrees_with_parse() {
+        for id in UlidGenerator::new().take(100) {
+            let mut bytes = [0; ULID_LEN];
+            bytes.copy_from_slice(id.to_string().as_bytes());
+            assert_eq!(Ulid::from_base32_bytes(&bytes), Ok(id));
+
+            bytes.make_ascii_lowercase();
+            assert_eq!(Ulid::from_base32_bytes(&bytes), Ok(id));
+        }
+    }
+
+    #[test]
+    fn decoding_bytes_rejects_bytes_outside_the_alphabet() {
+        let mut bytes = *b"01ARZ3NDEKTSV4RRFFQ69G5FAV";
+        for &b in &[b'U', b'-', 0x00, 0xff] {
+            bytes[7] = b;
+            assert_eq!(Ulid::from_base32_bytes(&bytes), Err(ParseError::InvalidChar { position: 7, byte: b }));
+        }
+        assert_eq!(Ulid::from_base32_bytes(b"81ARZ3NDEKTSV4RRFFQ69G5FAV"), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn parsing_in_place_agrees_with_parse() {
+        let texts: Vec<String> = UlidGenerator::new().take(100).map(|id| id.to_string()).collect();
+        let mut ids = vec![Ulid::new_nil(); texts.len()];
+
+        for (text, id) in texts.iter().zip(&mut ids) {
+            assert_eq!(Ulid::from_str_radix32_into(text, id), Ok(()));
+            assert_eq!(*id, text.parse::<Ulid>().unwrap());
+        }
+    }
+
+    #[test]
+    fn parsing_in_place_leaves_the_destination_alone_on_error() {
+        let mut id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(Ulid::from_str_radix32_into("01ARZ3NDEK", &mut id), Err(ParseError::InvalidLength { len: 10 }));
+        assert_eq!(Ulid::from_str_radix32_into("01ARZ3NDEKTSV4RRFFQ69G5FA!", &mut id), Err(ParseError::InvalidChar { position: 25, byte: b'!' }));
+        assert_eq!(id.to_string(), CANONICAL);
+    }
+
+    #[test]
+    fn canonical_parsing_rejects_lowercase_that_lenient_parsing_accepts() {
+        let lower = CANONICAL.to_ascii_lowercase();
+        assert_eq!(Ulid::from_str_canonical(&lower), Err(ParseError::InvalidChar { position: 2, byte: b'a' }));
+        assert_eq!(lower.parse::<Ulid>().unwrap().to_string(), CANONICAL);
+
+        let mixed = "01ARZ3NDEKTSV4RRFFQ69G5FAv";
+        assert_eq!(Ulid::from_str_canonical(mixed), Err(ParseError::InvalidChar { position: 25, byte: b'v' }));
+        assert!(mixed.parse::<Ulid>().is_ok());
+    }
+
+    #[test]
+    fn canonical_parsing_accepts_encoder_output_only() {
+        for id in UlidGenerator::new().take(100) {
+            assert_eq!(Ulid::from_str_canonical(&id.to_string()), Ok(id));
+        }
+
+        assert!(Ulid::from_str_canonical("01ARZ3NDEKTSV4RRFFQ69G5FAO").is_err());
+        assert!(Ulid::from_str_canonical("01ARZ3NDEKTSV4RRFFQ69G5FAL").is_err());
+    }
+
+    #[test]
+    fn hex_parsing_reverses_hex_formatting() {
+        for id in UlidGenerator::new().take(100) {
+            assert_eq!(Ulid::from_hex(&format!("{:032x}", id)).unwrap(), id);
+            assert_eq!(Ulid::from_hex(&format!("{:032X}", id)).unwrap(), id);
+            assert_eq!(Ulid::from_hex(&format!("{:#x}", id)).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn hex_parsing_accepts_short_input() {
+        assert_eq!(Ulid::from_hex("0"), Ok(Ulid::new_nil()));
+        assert_eq!(Ulid::from_hex("0xff").unwrap().bits, 0xff);
+    }
+
+    #[test]
+    fn hex_parsing_rejects_bad_input() {
+        assert_eq!(Ulid::from_hex(""), Err(ParseError::InvalidLength { len: 0 }));
+        assert_eq!(Ulid::from_hex("0x"), Err(ParseError::InvalidLength { len: 0 }));
+        assert_eq!(Ulid::from_hex(&"f".repeat(33)), Err(ParseError::InvalidLength { len: 33 }));
+        assert_eq!(Ulid::from_hex("+ff"), Err(ParseError::InvalidChar { position: 0, byte: b'+' }));
+        assert_eq!(Ulid::from_hex("0xfg"), Err(ParseError::InvalidChar { position: 1, byte: b'g' }));
+    }
+
+    #[test]
+    fn timestamps_can_be_read_in_several_units() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(id.timestamp_ms(), 1_469_922_850_259);
+        assert_eq!(id.timestamp_seconds(), 1_469_922_850);
+        assert_eq!(id.timestamp_nanos(), 1_469_922_850_259_000_000);
+    }
+
+    #[test]
+    fn generated_ulids_carry_the_current_time() {
+        let before = duration_since_epoch().as_millis() as u64;
+        let id = Ulid::new();
+        let after = duration_since_epoch().as_millis() as u64;
+        assert!(before <= id.timestamp_ms() && id.timestamp_ms() <= after);
+    }
+
+    #[test]
+    fn prefix_matching_agrees_with_the_canonical_string() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        for i in 0..=ULID_LEN {
+            assert!(id.starts_with(&CANONICAL[..i]));
+        }
+        assert!(!id.starts_with("01ARZ3NDEKTSV4RRFFQ69G5FAW"));
+        assert!(!id.starts_with("1"));
+    }
+
+    #[test]
+    fn prefix_matching_finds_ulids_by_timestamp() {
+        let mut gen = UlidGenerator::new();
+        let timestamp_prefix = &CANONICAL[..10];
+        for _ in 0..10 {
+            assert!(gen.ulid_at(1_469_922_850_259).starts_with(timestamp_prefix));
+            assert!(!gen.ulid_at(1_469_922_850_260).starts_with(timestamp_prefix));
+        }
+    }
+
+    #[test]
+    fn prefix_matching_handles_empty_and_overlong_prefixes() {
+        let id = Ulid::new();
+        assert!(id.starts_with(""));
+        assert!(!id.starts_with(&format!("{}0", id)));
+    }
+
+    #[test]
+    fn replacing_the_timestamp_keeps_the_randomness() {
+        for id in UlidGenerator::new().take(100) {
+            let moved = id.with_timestamp(1_469_922_850_259);
+            assert_eq!(moved.timestamp_ms(), 1_469_922_850_259);
+            assert_eq!(moved.random(), id.random());
+        }
+
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(id.with_timestamp(id.timestamp_ms()), id);
+        assert_eq!(id.with_timestamp(u64::MAX).timestamp_ms(), (1 << 48) - 1);
+    }
+
+    #[test]
+    fn adding_durations_moves_the_timestamp_forward() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        let later = id + Duration::from_secs(60);
+        assert_eq!(later.timestamp_ms(), id.timestamp_ms() + 60_000);
+        assert_eq!(later.random(), id.random());
+
+        assert_eq!(id + Duration::from_micros(999), id);
+    }
+
+    #[test]
+    fn subtracting_durations_moves_the_timestamp_backward() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        let earlier = id - Duration::from_millis(259);
+        assert_eq!(earlier.timestamp_ms(), 1_469_922_850_000);
+        assert_eq!(earlier.random(), id.random());
+        assert_eq!(earlier + Duration::from_millis(259), id);
+    }
+
+    #[test]
+    fn duration_arithmetic_saturates_at_the_timestamp_bounds() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+
+        let latest = id + Duration::from_secs(u64::MAX);
+        assert_eq!(latest.timestamp_ms(), (1 << 48) - 1);
+        assert_eq!(latest.random(), id.random());
+
+        let earliest = id - Duration::from_secs(u64::MAX);
+        assert_eq!(earliest.timestamp_ms(), 0);
+        assert_eq!(earliest.random(), id.random());
+    }
+
+    #[test]
+    fn components_reassemble_into_the_same_ulid() {
+        for id in UlidGenerator::new().take(100) {
+            let parts = id.components();
+            assert_eq!(parts.random, id.bits & RANDOM_MASK);
+            assert_eq!(Ulid::from(parts), id);
+        }
+    }
+
+    #[test]
+    fn binary_forms_roundtrip_in_both_byte_orders() {
+        for id in UlidGenerator::new().take(100) {
+            assert_eq!(Ulid::from_be_bytes(id.to_be_bytes()), id);
+            assert_eq!(Ulid::from_le_bytes(id.to_le_bytes()), id);
+        }
+    }
+
+    #[test]
+    fn ulids_equal_their_binary_and_ascii_forms() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert!(id == id.to_be_bytes());
+        assert!(id == *b"01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    }
+
+    #[test]
+    fn ulids_differ_from_other_binary_and_ascii_forms() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert!(id != id.to_le_bytes());
+        assert!(id != [0; ULID_BINARY_LEN]);
+        assert!(id != *b"01ARZ3NDEKTSV4RRFFQ69G5FAW");
+        assert!(id != *b"01arz3ndektsv4rrffq69g5fav");
+    }
+
+    #[test]
+    fn system_times_convert_to_ulids_with_the_same_timestamp() {
+        let now = SystemTime::now();
+        let since_epoch = now.duration_since(SystemTime::UNIX_EPOCH).unwrap();
+
+        let id = Ulid::from(now);
+        assert_eq!(id.timestamp_ms() as u128, since_epoch.as_millis());
+        assert_ne!(Ulid::from(now), id);
+    }
+
+    #[test]
+    fn the_default_ulid_is_nil() {
+        assert_eq!(Ulid::default(), Ulid::new_nil());
+        assert_eq!(Ulid::default(), Ulid::default());
+    }
+
+    #[test]
+    fn system_times_outside_of_the_timestamp_range_are_handled() {
+        let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(Ulid::from(before_epoch), Ulid::new_nil());
+
+        let far_future = SystemTime::UNIX_EPOCH + Duration::from_millis(1 << 50);
+        assert_eq!(Ulid::from(far_future).timestamp_ms(), (1 << 48) - 1);
+    }
+
+    #[test]
+    fn incrementing_the_counter_produces_increasing_ulids() {
+        let ms = 1_469_922_850_259;
+        let ids: Vec<Ulid> = (0..1_000).chain(u64::MAX - 1_000..=u64::MAX)
+            .map(|counter| Ulid::from_timestamp_and_counter(ms, counter))
+            .collect();
+
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|id| id.timestamp_ms() == ms));
+        assert_eq!(ids[0].random(), 0);
+        assert_eq!(ids.last().unwrap().random(), u64::MAX as u128);
+
+        // the next millisecond sorts after every counter value
+        assert!(Ulid::from_timestamp_and_counter(ms + 1, 0) > *ids.last().unwrap());
+    }
+
+    #[test]
+    fn distances_count_the_ids_in_between() {
+        let ms = 1_469_922_850_259;
+        let ids: Vec<Ulid> = (0..100).map(|n| Ulid::from_timestamp_and_counter(ms, n)).collect();
+
+        for (i, a) in ids.iter().enumerate() {
+            for (j, b) in ids.iter().enumerate() {
+                assert_eq!(a.distance(b), (i as i64 - j as i64).unsigned_abs() as u128);
+            }
+        }
+
+        let max = Ulid::from_u64s(u64::MAX, u64::MAX);
+        assert_eq!(Ulid::new_nil().distance(&max), u128::MAX);
+
+        // the next millisecond is 2^80 further on
+        let next_ms = Ulid::from_timestamp_and_counter(ms + 1, 0);
+        assert_eq!(ids[0].distance(&next_ms), 1 << 80);
+    }
+
+    #[test]
+    fn nth_after_adds_to_the_random_component() {
+        let ms = 1_469_922_850_259;
+        let id = Ulid::from_timestamp_and_counter(ms, 10);
+
+        assert_eq!(id.nth_after(0), Some(id));
+        let later = id.nth_after(1_000).unwrap();
+        assert_eq!(later.timestamp_ms(), ms);
+        assert_eq!(later.random(), 1_010);
+    }
+
+    #[test]
+    fn nth_after_rolls_into_the_timestamp() {
+        let ms = 1_469_922_850_259;
+        let last = Ulid::max_for_timestamp(ms);
+
+        let next = last.nth_after(1).unwrap();
+        assert_eq!(next, Ulid::min_for_timestamp(ms + 1));
+        assert_eq!(last.nth_after(1 << 80).unwrap().timestamp_ms(), ms + 1);
+        assert_eq!(last.nth_after(last.distance(&next)), Some(next));
+    }
+
+    #[test]
+    fn nth_after_returns_none_past_the_largest_ulid() {
+        let max = Ulid::from_u64s(u64::MAX, u64::MAX);
+        assert_eq!(max.nth_after(0), Some(max));
+        assert_eq!(max.nth_after(1), None);
+        assert_eq!(Ulid::new_nil().nth_after(u128::MAX), Some(max));
+        assert_eq!(Ulid::from_timestamp_and_counter(1, 0).nth_after(u128::MAX), None);
+    }
+
+    #[test]
+    fn random_masks_only_change_the_masked_bits() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+
+        let tagged = id.with_random_mask(0xffff, 0xbeef);
+        assert_eq!(tagged.timestamp_ms(), id.timestamp_ms());
+        assert_eq!(tagged.random() & 0xffff, 0xbeef);
+        assert_eq!(tagged.random() >> 16, id.random() >> 16);
+
+        // bits of value outside of mask are ignored
+        assert_eq!(id.with_random_mask(0xffff, u128::MAX).random() & 0xffff, 0xffff);
+        assert_eq!(id.with_random_mask(0xffff, u128::MAX).random() >> 16, id.random() >> 16);
+
+        // the whole random component can be replaced
+        let replaced = id.with_random_mask(RANDOM_MASK, 0);
+        assert_eq!(replaced, Ulid::min_for_timestamp(id.timestamp_ms()));
+        assert_eq!(id.with_random_mask(0, u128::MAX), id);
+    }
+
+    #[test]
+    #[should_panic(expected = "timestamp bits")]
+    fn random_masks_touching_the_timestamp_panic() {
+        let _ = Ulid::new().with_random_mask(1 << 80, 0);
+    }
+
+    #[test]
+    fn incrementing_the_timestamp_keeps_the_random_component() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        let later = id.try_increment_timestamp().unwrap();
+        assert_eq!(later.timestamp_ms(), id.timestamp_ms() + 1);
+        assert_eq!(later.random(), id.random());
+        assert!(later > Ulid::max_for_timestamp(id.timestamp_ms()));
+
+        let nil = Ulid::new_nil().try_increment_timestamp().unwrap();
+        assert_eq!(nil, Ulid::min_for_timestamp(1));
+    }
+
+    #[test]
+    fn incrementing_the_largest_timestamp_fails() {
+        let max_ms = (1 << 48) - 1;
+        assert_eq!(Ulid::min_for_timestamp(max_ms).try_increment_timestamp(), None);
+        assert_eq!(Ulid::from_u64s(u64::MAX, u64::MAX).try_increment_timestamp(), None);
+        assert!(Ulid::max_for_timestamp(max_ms - 1).try_increment_timestamp().is_some());
+    }
+
+    #[test]
+    fn far_future_ulids_are_detected() {
+        let far_future = Ulid::max_for_timestamp((1 << 48) - 1);
+        assert!(far_future.is_future(0));
+        assert!(far_future.is_future(u64::MAX >> 20));
+        assert!(!far_future.is_future(u64::MAX));
+
+        let now = Ulid::new();
+        assert!(!now.is_future(1_000));
+        assert!(!Ulid::new_nil().is_future(0));
+
+        let an_hour_ahead = now + Duration::from_secs(3_600);
+        assert!(an_hour_ahead.is_future(60_000));
+        assert!(!an_hour_ahead.is_future(7_200_000));
+    }
+
+    #[test]
+    fn lenient_input_is_canonicalized() {
+        assert_eq!(Ulid::canonicalize("  01arz3ndektsv4rrffq69g5fav  ").unwrap(), CANONICAL);
+        assert_eq!(Ulid::canonicalize(CANONICAL).unwrap(), CANONICAL);
+        assert_eq!(Ulid::canonicalize("\tOlARZ3NDEKTSV4RRFFQ69G5FAV\r\n").unwrap(), CANONICAL);
+        assert_eq!(Ulid::canonicalize("01ARZ3NDEKTSV4RRFFQ69G5FA"), Err(ParseError::InvalidLength { len: 25 }));
+        assert_eq!(
+            Ulid::canonicalize("01ARZ3NDEKTSV4RRFFQ69G5FAU"),
+            Err(ParseError::InvalidChar { position: 25, byte: b'U' })
+        );
+    }
+
+    #[test]
+    fn batches_are_decoded_with_a_result_for_each_input() {
+        let inputs = [
+            CANONICAL,
+            "",
+            "01ARZ3NDEKTSV4RRFFQ69G5FAU",
+            "01bx5zzkbkactav9wevgemmvs0",
+            "81ARZ3NDEKTSV4RRFFQ69G5FAV",
+            "01BX5ZZKBKACTAV9WEVGEMMVS0",
+        ];
+        let results = Ulid::decode_many(inputs.iter().copied());
+
+        assert_eq!(results.len(), inputs.len());
+        assert_eq!(results[0], CANONICAL.parse::<Ulid>());
+        assert_eq!(results[1], Err(ParseError::InvalidLength { len: 0 }));
+        assert_eq!(results[2], Err(ParseError::InvalidChar { position: 25, byte: b'U' }));
+        assert_eq!(results[3], "01BX5ZZKBKACTAV9WEVGEMMVS0".parse::<Ulid>());
+        assert_eq!(results[4], Err(ParseError::Overflow));
+        assert_eq!(results[5], results[3]);
+        assert!(Ulid::decode_many(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn nul_terminated_and_newline_terminated_buffers_parse() {
+        let expected: Ulid = CANONICAL.parse().unwrap();
+
+        let mut c_string = [0; ULID_LEN + 1];
+        c_string[..ULID_LEN].copy_from_slice(CANONICAL.as_bytes());
+        assert_eq!(Ulid::parse_nul_terminated(&c_string), Ok(expected));
+
+        let line = format!("{}\n", CANONICAL);
+        assert_eq!(Ulid::parse_nul_terminated(line.as_bytes()), Ok(expected));
+
+        let crlf = format!("{}\r\n", CANONICAL);
+        assert_eq!(Ulid::parse_nul_terminated(crlf.as_bytes()), Ok(expected));
+
+        // the rest of a larger C buffer, after the terminator, is ignored
+        let mut buf = [b'?'; 64];
+        buf[..ULID_LEN + 1].copy_from_slice(&c_string);
+        assert_eq!(Ulid::parse_nul_terminated(&buf), Ok(expected));
+        assert_eq!(Ulid::parse_nul_terminated(CANONICAL.as_bytes()), Ok(expected));
+    }
+
+    #[test]
+    fn nul_terminated_parsing_rejects_truncated_buffers() {
+        assert_eq!(Ulid::parse_nul_terminated(b"01ARZ3NDEK\0TSV4RRFFQ69G5FAV"), Err(ParseError::InvalidLength { len: 10 }));
+        assert_eq!(Ulid::parse_nul_terminated(b""), Err(ParseError::InvalidLength { len: 0 }));
+        assert_eq!(
+            Ulid::parse_nul_terminated(b"01ARZ3NDEKTSV4RRFFQ69G5FAV\n\n"),
+            Err(ParseError::InvalidLength { len: 27 })
+        );
+    }
+
+    #[test]
+    fn appended_ulids_parse_back() {
+        use core::convert::TryInto;
+
+        let ids: Vec<Ulid> = UlidGenerator::from_seed(427).take(20).collect();
+
+        let mut csv = Vec::new();
+        for id in &ids {
+            id.append_to(&mut csv);
+            csv.push(b'\n');
+        }
+        assert_eq!(csv.len(), ids.len() * (ULID_LEN + 1));
+
+        let parsed: Vec<Ulid> = csv
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| Ulid::from_base32_bytes(line.try_into().unwrap()).unwrap())
+            .collect();
+        assert_eq!(parsed, ids);
+    }
+
+    #[test]
+    fn display_output_matches_the_write_macro_byte_for_byte() {
+        struct ViaWriteMacro(Ulid);
+
+        impl Display for ViaWriteMacro {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let buf = <[u8; ULID_LEN]>::from(self.0);
+                write!(f, "{}", core::str::from_utf8(&buf).unwrap())
+            }
+        }
+
+        let mut gen = UlidGenerator::from_seed(439);
+        for id in gen.by_ref().take(100).chain(vec![Ulid::new_nil(), Ulid::from_u64s(u64::MAX, u64::MAX)]) {
+            assert_eq!(id.to_string().as_bytes(), ViaWriteMacro(id).to_string().as_bytes());
+            assert_eq!(format!("{:>30}|{:<30}|{:^30}", id, id, id), format!("{:>30}|{:<30}|{:^30}", ViaWriteMacro(id), ViaWriteMacro(id), ViaWriteMacro(id)));
+            assert_eq!(format!("{:*>30}", id), id.to_string());
+        }
+    }
+
+    #[test]
+    fn ulids_convert_into_strings() {
+        fn name(value: impl Into<String>) -> String {
+            value.into()
+        }
+
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(String::from(id), CANONICAL);
+        assert_eq!(String::from(&id), CANONICAL);
+        assert_eq!(name(id), CANONICAL);
+
+        let ids = [id, Ulid::new_nil()];
+        let names: Vec<String> = ids.iter().map(name).collect();
+        assert_eq!(names, [CANONICAL, "00000000000000000000000000"]);
+    }
+
+    #[test]
+    fn many_ulids_can_be_written_into_one_string() {
+        let ids: Vec<Ulid> = UlidGenerator::from_seed(415).take(50).collect();
+
+        let mut written = String::new();
+        for id in &ids {
+            id.write_canonical(&mut written).unwrap();
+        }
+
+        let concatenated: String = ids.iter().map(|id| id.to_string()).collect();
+        assert_eq!(written, concatenated);
+    }
+
+    #[test]
+    fn words_round_trip() {
+        let mut gen = UlidGenerator::from_seed(430);
+        for id in gen.by_ref().take(100).chain(vec![Ulid::new_nil(), Ulid::from_u64s(u64::MAX, u64::MAX)]) {
+            assert_eq!(Ulid::from_words(id.to_words()), id);
+        }
+    }
+
+    #[test]
+    fn words_are_most_significant_first() {
+        let id = Ulid { bits: 0x0011_2233_4455_6677_8899_aabb_ccdd_eeff };
+        assert_eq!(id.to_words(), [0x0011_2233, 0x4455_6677, 0x8899_aabb, 0xccdd_eeff]);
+        assert_eq!(Ulid::from_words([1, 0, 0, 0]), Ulid { bits: 1 << 96 });
+        assert_eq!(Ulid::from_words([0, 0, 0, 1]), Ulid { bits: 1 });
+    }
+
+    #[test]
+    fn storage_columns_round_trip() {
+        let mut gen = UlidGenerator::from_seed(425);
+        for id in gen.by_ref().take(100).chain(vec![Ulid::new_nil(), Ulid::from_u64s(u64::MAX, u64::MAX)]) {
+            let (ts, random) = id.split_for_storage();
+            assert_eq!(ts as u64, id.timestamp_ms());
+            assert_eq!(u128::from_be_bytes({
+                let mut padded = [0; 16];
+                padded[6..].copy_from_slice(&random);
+                padded
+            }), id.random());
+            assert_eq!(Ulid::from_storage(ts, random), Ok(id));
+        }
+    }
+
+    #[test]
+    fn storage_timestamps_outside_of_48_bits_are_rejected() {
+        let random = [0xab; 10];
+        assert_eq!(Ulid::from_storage(-1, random), Err(TimestampOutOfRange));
+        assert_eq!(Ulid::from_storage(i64::MIN, random), Err(TimestampOutOfRange));
+        assert_eq!(Ulid::from_storage(1 << 48, random), Err(TimestampOutOfRange));
+        assert_eq!(Ulid::from_storage(i64::MAX, random), Err(TimestampOutOfRange));
+        assert_eq!(Ulid::from_storage((1 << 48) - 1, random).unwrap().timestamp_ms(), (1 << 48) - 1);
+        assert_eq!(Ulid::from_storage(0, random).unwrap().timestamp_ms(), 0);
+    }
+
+    #[test]
+    fn newest_first_sorts_in_descending_order() {
+        let mut gen = UlidGenerator::from_seed(429);
+        let ids: Vec<Ulid> = (0..50).map(|i| gen.ulid_at(1_469_922_850_259 + (i * 37) % 50)).collect();
+
+        let mut wrapped: Vec<NewestFirst> = ids.iter().copied().map(NewestFirst).collect();
+        wrapped.sort();
+        let unwrapped: Vec<Ulid> = wrapped.into_iter().map(Ulid::from).collect();
+        assert!(unwrapped.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let mut by_comparator = ids.clone();
+        by_comparator.sort_by(Ulid::cmp_newest_first);
+        assert_eq!(by_comparator, unwrapped);
+
+        let mut reversed = ids;
+        reversed.sort();
+        reversed.reverse();
+        assert_eq!(reversed, unwrapped);
+    }
+
+    #[test]
+    fn fixed_hashes_never_change() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(id.fixed_hash(), 0x4cc5_4d9b_81e1_83a0);
+        assert_eq!(id.fixed_hash(), id.fixed_hash());
+        assert_eq!(Ulid::new_nil().fixed_hash(), 0x8820_1fb9_60ff_6465);
+        assert_ne!(Ulid { bits: id.bits ^ 1 }.fixed_hash(), id.fixed_hash());
+    }
+
+    #[test]
+    fn framed_bytes_round_trip() {
+        let mut ulid_gen = UlidGenerator::from_seed(409);
+        for id in ulid_gen.by_ref().take(100).chain(vec![Ulid::new_nil(), Ulid::from_u64s(u64::MAX, u64::MAX)]) {
+            let framed = id.to_framed_bytes();
+            assert_eq!(framed[0], FRAMED_VERSION);
+            assert_eq!(framed[1..], id.to_be_bytes());
+            assert_eq!(Ulid::from_framed_bytes(&framed), Ok(id));
+        }
+    }
+
+    #[test]
+    fn framed_bytes_with_an_unknown_version_are_rejected() {
+        let mut framed = Ulid::new().to_framed_bytes();
+        for version in [0, 2, 0xff] {
+            framed[0] = version;
+            assert_eq!(Ulid::from_framed_bytes(&framed), Err(UnsupportedVersion(version)));
+        }
+        assert_eq!(UnsupportedVersion(2).to_string(), "unsupported framed ULID version: 2");
+    }
+
+    #[test]
+    fn zero_padded_32_byte_buffers_decode_their_low_half() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        let mut buf = [0; 32];
+        buf[16..].copy_from_slice(&id.to_be_bytes());
+        assert_eq!(Ulid::from_32_bytes_checked(buf), Ok(id));
+        assert_eq!(Ulid::from_32_bytes_checked([0; 32]), Ok(Ulid::new_nil()));
+    }
+
+    #[test]
+    fn non_zero_padding_in_32_byte_buffers_is_rejected() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        for i in [0, 7, 15] {
+            let mut buf = [0; 32];
+            buf[16..].copy_from_slice(&id.to_be_bytes());
+            buf[i] = 1;
+            assert_eq!(Ulid::from_32_bytes_checked(buf), Err(NonZeroPadding));
+        }
+
+        // a ULID in the high half is a caller bug, not a valid encoding
+        let mut buf = [0; 32];
+        buf[..16].copy_from_slice(&id.to_be_bytes());
+        assert_eq!(Ulid::from_32_bytes_checked(buf), Err(NonZeroPadding));
+        assert_eq!(NonZeroPadding.to_string(), "padding before the ULID is not zero");
+    }
+
+    #[test]
+    fn byte_order_detection_reads_both_orders_of_the_same_ulid() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(Ulid::from_bytes_autodetect(&id.to_be_bytes()), id);
+        assert_eq!(Ulid::from_bytes_autodetect(&id.to_le_bytes()), id);
+
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let ids: Vec<Ulid> = gen.by_ref().take(1_000).collect();
+        assert!(ids.iter().all(|id| Ulid::from_bytes_autodetect(&id.to_be_bytes()) == *id));
+
+        // about 1% of little-endian ULIDs are ambiguous
+        let detected = ids
+            .iter()
+            .filter(|id| Ulid::from_bytes_autodetect(&id.to_le_bytes()) == **id)
+            .count();
+        assert!(detected > 950, "{}", detected);
+    }
+
+    #[test]
+    fn byte_order_detection_falls_back_to_big_endian() {
+        let ancient = Ulid::min_for_timestamp(1_000);
+        assert_eq!(Ulid::from_bytes_autodetect(&ancient.to_be_bytes()), ancient);
+        assert_eq!(
+            Ulid::from_bytes_autodetect(&ancient.to_le_bytes()),
+            Ulid::from_be_bytes(ancient.to_le_bytes())
+        );
+    }
+
+    #[test]
+    fn big_endian_bytes_start_with_the_timestamp() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        let be = id.to_be_bytes();
+        let le = id.to_le_bytes();
+
+        assert_ne!(be, le);
+        assert_eq!(be[..6], 1_469_922_850_259_u64.to_be_bytes()[2..]);
+        assert_eq!(be.iter().rev().collect::<Vec<_>>(), le.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn short_ulids_roundtrip_through_strings() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        for random_chars in 0..=16 {
+            let short = ShortUlid::new(id, random_chars);
+            let text = short.to_string();
+            assert_eq!(text, &CANONICAL[..10 + random_chars]);
+            assert_eq!(text.parse(), Ok(short));
+            assert_eq!(short.timestamp_ms(), id.timestamp_ms());
+        }
+        assert_eq!(ShortUlid::new(id, 16).to_ulid(), id);
+    }
+
+    #[test]
+    fn short_ulids_sort_by_time() {
+        let mut gen = UlidGenerator::new();
+        let older = ShortUlid::new(gen.ulid_at(1_469_922_850_259), 4);
+        let newer = ShortUlid::new(gen.ulid_at(1_469_922_850_260), 4);
+        assert!(older < newer);
+        assert!(older.to_string() < newer.to_string());
+    }
+
+    #[test]
+    fn short_ulids_reject_bad_lengths() {
+        assert_eq!("01ARZ3NDE".parse::<ShortUlid>(), Err(ParseError::InvalidLength { len: 9 }));
+        assert_eq!(format!("{}0", CANONICAL).parse::<ShortUlid>(), Err(ParseError::InvalidLength { len: 27 }));
+    }
+
+    #[test]
+    fn ascii_arrays_match_display() {
+        for id in UlidGenerator::new().take(100) {
+            let ascii: [u8; ULID_LEN] = id.into();
+            assert_eq!(std::str::from_utf8(&ascii).unwrap(), id.to_string());
+        }
+    }
+
+    #[test]
+    fn reverse_table_inverts_the_alphabet() {
+        for value in 0..32u8 {
+            let b = base32::char_at(value as u128, ULID_LEN - 1);
+            assert_eq!(BASE32_REVERSE[b as usize], value as i8);
+            assert_eq!(BASE32_REVERSE[b.to_ascii_lowercase() as usize], value as i8);
+        }
+
+        // confusable characters decode as they normalize
+        for &b in b"IiLlOo" {
+            let normalized = normalize_crockford(std::str::from_utf8(&[b]).unwrap());
+            assert_eq!(BASE32_REVERSE[b as usize], BASE32_REVERSE[normalized.as_bytes()[0] as usize]);
+        }
+        assert_eq!(BASE32_REVERSE[b'I' as usize], 1);
+        assert_eq!(BASE32_REVERSE[b'l' as usize], 1);
+        assert_eq!(BASE32_REVERSE[b'o' as usize], 0);
+
+        let valid = BASE32_REVERSE.iter().filter(|&&v| v >= 0).count();
+        assert_eq!(valid, 32 + 22 + 6); // digits are caseless
+        for &b in b"Uu-" {
+            assert_eq!(BASE32_REVERSE[b as usize], -1);
+        }
+    }
+
+    #[test]
+    fn timestamp_strings_are_prefixes_of_the_canonical_string() {
+        let mut buf = [0; TIMESTAMP_LEN];
+        for id in UlidGenerator::new().take(100) {
+            assert_eq!(id.timestamp_str(&mut buf), &id.to_string()[..TIMESTAMP_LEN]);
+        }
+    }
+
+    #[test]
+    fn checked_strings_roundtrip() {
+        for id in UlidGenerator::new().take(100) {
+            let checked = id.to_string_with_check();
+            assert_eq!(&checked[..ULID_LEN], id.to_string());
+            assert_eq!(Ulid::from_str_with_check(&checked), Ok(id));
+            assert_eq!(Ulid::from_str_with_check(&checked.to_ascii_lowercase()), Ok(id));
+        }
+    }
+
+    #[test]
+    fn checked_strings_detect_single_character_changes() {
+        let checked = CANONICAL.parse::<Ulid>().unwrap().to_string_with_check();
+
+        // skip the first character, where most changes overflow 128 bits
+        for i in 1..ULID_LEN {
+            for &replacement in b"0123456789ABCDEFGHJKMNPQRSTVWXYZ" {
+                let mut altered = checked.clone().into_bytes();
+                if altered[i] == replacement {
+                    continue;
+                }
+                altered[i] = replacement;
+                let altered = String::from_utf8(altered).unwrap();
+                assert_eq!(Ulid::from_str_with_check(&altered), Err(ParseError::ChecksumMismatch));
+            }
+        }
+    }
+
+    #[test]
+    fn checked_strings_need_the_check_symbol() {
+        assert_eq!(Ulid::from_str_with_check(CANONICAL), Err(ParseError::InvalidLength { len: 26 }));
+        assert_eq!(base32::check_symbol(32), b'*');
+        assert_eq!(base32::check_symbol(36), b'U');
+    }
+
+    #[test]
+    fn grouped_strings_roundtrip() {
+        for id in UlidGenerator::new().take(20) {
+            for &(size, sep) in &[(8, '-'), (4, ' '), (5, '·'), (26, '-'), (0, '-')] {
+                let grouped = id.to_grouped_string(size, sep);
+                assert_eq!(grouped.chars().filter(|&c| c != sep).collect::<String>(), id.to_string());
+                assert_eq!(Ulid::parse_grouped(&grouped, sep), Ok(id));
+            }
+        }
+    }
+
+    #[test]
+    fn grouped_parsing_rejects_other_separators() {
+        let grouped = "01ARZ3ND-EKTSV4RR-FFQ69G5F-AV";
+        assert_eq!(Ulid::parse_grouped(grouped, '-'), CANONICAL.parse());
+        assert_eq!(Ulid::parse_grouped(grouped, ' '), Err(ParseError::InvalidLength { len: 29 }));
+        assert_eq!(Ulid::parse_grouped("01ARZ3ND EKTSV4RR FFQ69G5F", ' '), Err(ParseError::InvalidLength { len: 24 }));
+    }
+
+    #[test]
+    fn checked_u128_conversion_accepts_timestamps_in_range() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(Ulid::try_from_u128_checked(id.bits, id.timestamp_ms()), Ok(id));
+        assert_eq!(Ulid::try_from_u128_checked(id.bits, u64::MAX), Ok(id));
+        assert_eq!(Ulid::try_from_u128_checked(0, 0), Ok(Ulid::new_nil()));
+    }
+
+    #[test]
+    fn checked_u128_conversion_rejects_later_timestamps() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(
+            Ulid::try_from_u128_checked(id.bits, id.timestamp_ms() - 1),
+            Err(TimestampOutOfRange)
+        );
+        assert_eq!(Ulid::try_from_u128_checked(u128::MAX, 1 << 47), Err(TimestampOutOfRange));
+    }
+
+    #[test]
+    fn sequential_ulids_spread_evenly_across_shards() {
+        const SHARDS: u32 = 16;
+        let mut gen = frozen_generator(1_469_922_850_259);
+
+        let mut by_random = [0; SHARDS as usize];
+        let mut by_prefix = [0; SHARDS as usize];
+        for _ in 0..16_000 {
+            let id = gen.next_monotonic().unwrap();
+            by_random[id.shard(SHARDS) as usize] += 1;
+            by_prefix[(id.timestamp_ms() % SHARDS as u64) as usize] += 1;
+        }
+
+        assert!(by_random.iter().all(|&n| (800..1_200).contains(&n)), "{:?}", by_random);
+        assert_eq!(by_prefix.iter().filter(|&&n| n > 0).count(), 1);
+    }
+
+    #[test]
+    fn shards_are_stable_and_in_range() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(id.shard(1), 0);
+        assert_eq!(id.shard(1_000), id.shard(1_000));
+        assert_eq!(id.shard(u32::MAX), id.with_timestamp(0).shard(u32::MAX));
+        for n in 1..100 {
+            assert!(id.shard(n) < n);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "num_shards")]
+    fn sharding_into_nothing_panics() {
+        let _ = Ulid::new().shard(0);
+    }
+
+    #[test]
+    fn total_cmp_agrees_with_every_other_ordering() {
+        use itertools::Itertools;
+
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let mut ids: Vec<Ulid> = gen.by_ref().take(10).collect();
+        ids.extend((0..10).map(|ms| gen.ulid_at(ms)));
+        ids.push(ids[0]);
+
+        for (a, b) in ids.iter().tuple_combinations() {
+            let ordering = a.total_cmp(b);
+            assert_eq!(ordering, a.cmp(b));
+            assert_eq!(ordering == Ordering::Less, a < b);
+            assert_eq!(ordering == Ordering::Greater, a > b);
+            assert_eq!(ordering, a.to_be_bytes().cmp(&b.to_be_bytes()));
+            assert_eq!(ordering, a.to_string().cmp(&b.to_string()));
+        }
+    }
+
+    #[test]
+    fn latest_and_earliest_find_the_extremes() {
+        use itertools::Itertools;
+
+        let mut gen = UlidGenerator::new();
+        let ids: Vec<Ulid> = (0..20).map(|ms| gen.ulid_at(1_469_922_850_000 + ms)).collect();
+
+        // interleave the two halves, so neither extreme is at either end
+        let (old, new) = ids.split_at(10);
+        let shuffled: Vec<Ulid> = new.iter().interleave(old.iter().rev()).copied().collect();
+        assert_ne!(shuffled.first(), Some(&ids[0]));
+        assert_ne!(shuffled.last(), Some(&ids[19]));
+
+        assert_eq!(Ulid::latest(shuffled.iter().copied()), Some(ids[19]));
+        assert_eq!(Ulid::earliest(shuffled), Some(ids[0]));
+    }
+
+    #[test]
+    fn latest_and_earliest_of_nothing_is_none() {
+        assert_eq!(Ulid::latest(Vec::new()), None);
+        assert_eq!(Ulid::earliest(std::iter::empty()), None);
+    }
+
+    #[test]
+    fn u64_halves_roundtrip() {
+        for id in UlidGenerator::new().take(100) {
+            let (hi, lo) = id.split_u64s();
+            assert_eq!(hi >> 16, id.timestamp_ms());
+            assert_eq!(Ulid::from_u64s(hi, lo), id);
+        }
+
+        let id = Ulid::from_u64s(0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210);
+        assert_eq!(format!("{:x}", id), "123456789abcdeffedcba9876543210");
+    }
+
+    #[test]
+    fn scanning_bytes_finds_ulids_among_noise() {
+        let mut buf = vec![0xfe, 0x00, b' '];
+        buf.extend_from_slice(b"01ARZ3NDEKTSV4RRFFQ69G5FAVX "); // too long
+        buf.extend_from_slice(b"ZZARZ3NDEKTSV4RRFFQ69G5FAV "); // overflows
+        buf.extend_from_slice(b"01ARZ3NDEKTSV4RRFFQ69G5FA "); // too short
+        buf.extend_from_slice(b"[01ARZ3NDEKTSV4RRFFQ69G5FAV]");
+        buf.extend_from_slice(&[0x80, 0xff]);
+
+        assert_eq!(Ulid::scan_bytes(&buf), vec![CANONICAL.parse::<Ulid>().unwrap()]);
+        assert!(Ulid::scan_bytes(b"").is_empty());
+    }
+
+    #[test]
+    fn scanning_bytes_finds_adjacent_ulids() {
+        let ids: Vec<_> = UlidGenerator::new().take(5).collect();
+        let text = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join("\n");
+        assert_eq!(Ulid::scan_bytes(text.as_bytes()), ids);
+    }
+
+    #[test]
+    fn timestamps_format_as_iso8601() {
+        let mut gen = UlidGenerator::new();
+        let cases = [
+            (0, "1970-01-01T00:00:00.000Z"),
+            (951_782_400_000, "2000-02-29T00:00:00.000Z"),
+            (1_469_922_850_259, "2016-07-30T23:54:10.259Z"),
+            (1_709_251_199_999, "2024-02-29T23:59:59.999Z"),
+            ((1 << 48) - 1, "10889-08-02T05:31:50.655Z"),
+        ];
+
+        for &(ms, expected) in &cases {
+            assert_eq!(gen.ulid_at(ms).timestamp_iso8601(), expected);
+        }
+    }
+
+    #[test]
+    fn comparing_against_strings_agrees_with_parsing() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        assert_eq!(id.cmp_str(CANONICAL), Ok(Ordering::Equal));
+        assert_eq!(id.cmp_str("01arz3ndektsv4rrffq69g5fav"), Ok(Ordering::Equal));
+        assert_eq!(id.cmp_str("01ARZ3NDEKTSV4RRFFQ69G5FAT"), Ok(Ordering::Greater));
+        assert_eq!(id.cmp_str("01ARZ3NDEKTSV4RRFFQ69G5FAW"), Ok(Ordering::Less));
+        assert_eq!(id.cmp_str("00ZZZZZZZZZZZZZZZZZZZZZZZZ"), Ok(Ordering::Greater));
+
+        for other in UlidGenerator::new().take(50) {
+            assert_eq!(id.cmp_str(&other.to_string()), Ok(id.cmp(&other)));
+        }
+    }
+
+    #[test]
+    fn comparing_against_invalid_strings_fails() {
+        let id = Ulid::new();
+        assert_eq!(id.cmp_str("01ARZ3NDEK"), Err(ParseError::InvalidLength { len: 10 }));
+        assert_eq!(id.cmp_str("01ARZ3NDEKTSV4RRFFQ69G5FAU"), Err(ParseError::InvalidChar { position: 25, byte: b'U' }));
+        assert_eq!(id.cmp_str("81ARZ3NDEKTSV4RRFFQ69G5FAV"), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn ulid_macro_agrees_with_parsing() {
+        const ID: Ulid = crate::ulid!("01ARZ3NDEKTSV4RRFFQ69G5FAV");
+        assert_eq!(Ok(ID), CANONICAL.parse());
+        assert_eq!(crate::ulid!("01arz3ndektsv4rrffq69g5fav"), ID);
+    }
+
+    #[test]
+    fn default_parser_agrees_with_from_str() {
+        let parser = UlidParser::new();
+        let inputs = [
+            CANONICAL,
+            "01arz3ndektsv4rrffq69g5fav",
+            "O1ARZ3NDEKTSV4RRFFQ69G5FAV",
+            " 01ARZ3NDEKTSV4RRFFQ69G5FAV",
+        ];
+        for input in &inputs {
+            assert_eq!(parser.parse(input), input.parse::<Ulid>());
+        }
+    }
+
+    #[test]
+    fn parser_can_be_case_sensitive() {
+        let parser = UlidParser::new().case_insensitive(false);
+        assert!(parser.parse(CANONICAL).is_ok());
+        assert_eq!(parser.parse("01arz3ndektsv4rrffq69g5fav"), Err(ParseError::InvalidChar { position: 2, byte: b'a' }));
+    }
+
+    #[test]
+    fn parser_can_normalize_confusables() {
+        let input = "OIARZ3NDEKTSV4RRFFQ69G5FAV";
+        assert_eq!(UlidParser::new().parse(input), Err(ParseError::InvalidChar { position: 0, byte: b'O' }));
+
+        let parser = UlidParser::new().normalize_confusables(true);
+        assert_eq!(parser.parse(input), CANONICAL.parse());
+        assert_eq!(parser.parse("oLARZ3NDEKTSV4RRFFQ69G5FAV"), CANONICAL.parse());
+    }
+
+    #[test]
+    fn parser_can_trim_whitespace() {
+        let input = "\t01ARZ3NDEKTSV4RRFFQ69G5FAV\n";
+        assert_eq!(UlidParser::new().parse(input), Err(ParseError::InvalidLength { len: 28 }));
+        assert_eq!(UlidParser::new().trim(true).parse(input), CANONICAL.parse());
+    }
+
+    #[test]
+    fn generators_are_unbounded_iterators() {
+        let mut gen = UlidGenerator::new();
+        assert_eq!(gen.size_hint(), (usize::MAX, None));
+        assert!((0..1000).all(|_| gen.next().is_some()));
+    }
+
+    #[test]
+    fn entropy_seeded_generators_work() {
+        let mut a = UlidGenerator::new_from_entropy_or_clock();
+        let mut b = UlidGenerator::new_from_entropy_or_clock();
+        assert_ne!(a.ulid(), b.ulid());
+        assert!(a.next_monotonic().unwrap() < a.next_monotonic().unwrap());
+    }
+
+    struct MicrosClock(core::cell::Cell<u64>);
+
+    impl Clock for MicrosClock {
+        fn now_ms(&self) -> u64 {
+            self.now_micros() / 1_000
+        }
+
+        // each reading is 90µs after the previous one
+        fn now_micros(&self) -> u64 {
+            let now = self.0.get();
+            self.0.set(now + 90);
+            now
+        }
+    }
+
+    #[test]
+    fn submillis_ordering_sorts_ulids_within_a_millisecond() {
+        let rng: Xoroshiro128 = SeedableRng::from_seed(&[1, 2][..]);
+        let clock = MicrosClock(core::cell::Cell::new(1_469_922_850_259_000));
+        let mut gen = UlidGenerator::from_rng_and_clock(rng, clock).with_submillis_ordering();
+
+        let ids: Vec<Ulid> = gen.by_ref().take(11).collect();
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(id.timestamp_ms(), 1_469_922_850_259);
+            assert_eq!(id.random() >> 70, 90 * i as u128);
+        }
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+        // the 13th reading is in the next millisecond
+        assert_eq!(gen.ulid().random() >> 70, 990);
+        let next = gen.ulid();
+        assert_eq!(next.timestamp_ms(), 1_469_922_850_260);
+        assert_eq!(next.random() >> 70, 80);
+    }
+
+    #[test]
+    fn submillis_ordering_comes_after_the_node_id() {
+        let rng: Xoroshiro128 = SeedableRng::from_seed(&[1, 2][..]);
+        let clock = MicrosClock(core::cell::Cell::new(1_469_922_850_259_123));
+        let mut gen = UlidGenerator::from_rng_and_clock(rng, clock)
+            .with_node_id(7)
+            .with_submillis_ordering();
+
+        let id = gen.ulid();
+        assert_eq!(id.random() >> 64, 7);
+        assert_eq!((id.random() >> 54) & 0x3ff, 123);
+    }
+
+    #[test]
+    fn closures_can_supply_the_random_bits() {
+        let values = [0x1234_5678_9abc_def0, 0x0fed_cba9_8765_4321];
+        let mut gen = UlidGenerator::from_closure(move || values[0]);
+        assert_eq!(gen.ulid().random(), 0xdef0_1234_5678_9abc_def0);
+
+        // a clock that moves on keeps each ULID from incrementing the last
+        let mut values = values.iter().cycle();
+        let closure = FnRng(move || *values.next().unwrap());
+        let mut gen = UlidGenerator::from_rng_and_clock(closure, SteppingClock::new(0, 1));
+        for _ in 0..3 {
+            assert_eq!(gen.ulid().random(), 0xdef0_0fed_cba9_8765_4321);
+        }
+    }
+
+    #[test]
+    fn skipping_ulids_matches_generating_and_discarding_them() {
+        for &n in &[0, 1, 5, 100] {
+            let stepping = || UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(1234), SteppingClock::new(0, 1));
+            let mut skipped = stepping();
+            skipped.skip_ulids(n);
+
+            let expected = stepping().nth(n as usize).unwrap();
+            assert_eq!(skipped.ulid().random(), expected.random());
+        }
+    }
+
+    #[test]
+    fn skipping_ulids_keeps_the_increment_state_and_stats() {
+        let frozen = || UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(1234), FixedClock(1_469_922_850_259));
+        for &n in &[0, 1, 5, 100] {
+            let mut skipped = frozen();
+            skipped.skip_ulids(n);
+
+            let mut generated = frozen();
+            let expected = generated.nth(n as usize).unwrap();
+            assert_eq!(skipped.ulid(), expected);
+            assert_eq!(skipped.stats(), generated.stats());
+        }
+    }
+
+    #[test]
+    fn skipping_ulids_from_a_seed_matches_generating_them() {
+        // The two sequences only match when neither saw the clock move on
+        // part way through, so try until one attempt runs within a single
+        // millisecond
+        for _ in 0..1_000 {
+            let generated: Vec<Ulid> = UlidGenerator::from_seed(1234).take(6).collect();
+
+            let mut skipped = UlidGenerator::from_seed(1234);
+            let before = skipped.peek_timestamp();
+            skipped.skip_ulids(5);
+            let next = skipped.ulid();
+
+            let ms = generated[0].timestamp_ms();
+            if generated[5].timestamp_ms() != ms || before != ms || next.timestamp_ms() != ms {
+                continue;
+            }
+            assert_eq!(next, generated[5]);
+            assert_eq!(skipped.generated_count(), 6);
+            return;
+        }
+        panic!("the clock moved on during every attempt");
+    }
+
+    #[test]
+    fn custom_epochs_roundtrip() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800); // 2020-01-01
+        let now = SystemTime::UNIX_EPOCH + Duration::from_millis(1_600_000_000_123);
+
+        let mut gen = frozen_generator(1_600_000_000_123).with_epoch(epoch);
+        let id = gen.ulid();
+        assert_eq!(id.timestamp_ms(), 1_600_000_000_123 - 1_577_836_800_000);
+        assert_eq!(id.datetime_with_epoch(epoch), now);
+        assert_ne!(id.datetime(), now);
+
+        let id = gen.next_monotonic().unwrap();
+        assert_eq!(id.datetime_with_epoch(epoch), now);
+        assert_eq!(gen.peek_timestamp(), id.timestamp_ms());
+    }
+
+    #[test]
+    fn clock_readings_before_a_custom_epoch_are_clamped() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800);
+        let mut gen = frozen_generator(1_469_922_850_259).with_epoch(epoch);
+        assert_eq!(gen.ulid().timestamp_ms(), 0);
+    }
+
+    #[test]
+    fn generators_describe_their_configuration() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800);
+        let mut gen = frozen_generator(1_600_000_000_000)
+            .with_node_id(42)
+            .with_submillis_ordering()
+            .with_epoch(epoch)
+            .with_reserved_sentinels()
+            .with_duplicate_detection(100);
+
+        let before = gen.to_string();
+        assert_eq!(
+            before,
+            "UlidGenerator (ordering: sub-millisecond, node id: 42, epoch: UNIX + 1577836800000 ms, \
+             sentinels: reserved, duplicate window: 100, generated: 0)"
+        );
+
+        // generating doesn't change the configuration that is described
+        let _ = gen.ulid();
+        let _ = gen.next_monotonic().unwrap();
+        assert_eq!(gen.to_string(), before.replace("generated: 0", "generated: 2"));
+    }
+
+    #[test]
+    fn generators_keep_their_prng_state_out_of_logs() {
+        let gen = frozen_generator(1_469_922_850_259);
+        let debug = format!("{:?}", gen);
+        assert_eq!(
+            debug,
+            "UlidGenerator { submillis: false, node_id: None, epoch_ms: 0, reserve_sentinels: false, \
+             duplicate_window: None, stats: GenStats { generated: 0, monotonic_increments: 0, clock_backwards: 0 }, .. }"
+        );
+
+        // nothing refers to the PRNG
+        for text in &[debug, gen.to_string()] {
+            assert!(!text.contains("rng"));
+            assert!(!text.contains("Xoroshiro"));
+        }
+    }
+
+    #[test]
+    fn deterministic_generators_repeat_themselves() {
+        let expected = [
+            "00000000006EHG122PCYRS6JZT",
+            "0000000001TQAKZ2Z492BT8CBW",
+            "0000000002WTNXDD9FADWXW7F0",
+        ];
+        let ids: Vec<String> = UlidGenerator::deterministic().take(3).map(|id| id.to_string()).collect();
+        assert_eq!(ids, expected);
+
+        let again: Vec<String> = UlidGenerator::deterministic().take(3).map(|id| id.to_string()).collect();
+        assert_eq!(again, ids);
+    }
+
+    #[test]
+    fn generators_count_the_ulids_they_produce() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        assert_eq!(gen.generated_count(), 0);
+
+        for expected in 1..=3 {
+            let _ = gen.ulid();
+            assert_eq!(gen.generated_count(), expected);
+        }
+
+        let _ = gen.next_monotonic().unwrap();
+        let _ = gen.ulid_at(0);
+        let _ = gen.ulids_for_ms(0, 5);
+        assert_eq!(gen.generated_count(), 10);
+    }
+
+    struct EpochlessClock;
+
+    impl Clock for EpochlessClock {
+        fn now_ms(&self) -> u64 {
+            panic!("ulid_checked() must not reach the panicking path");
+        }
+
+        fn try_now_ms(&self) -> Result<u64, ClockError> {
+            Err(ClockError)
+        }
+    }
+
+    #[test]
+    fn checked_generation_reports_clock_failures() {
+        let rng: Xoroshiro128 = SeedableRng::from_seed(&[1, 2][..]);
+        let mut gen = UlidGenerator::from_rng_and_clock(rng, EpochlessClock);
+        assert_eq!(gen.ulid_checked(), Err(GenError::Clock(ClockError)));
+        assert_eq!(gen.next_monotonic_checked(), Err(GenError::Clock(ClockError)));
+        assert_eq!(gen.generated_count(), 0);
+
+        let err: &dyn std::error::Error = &GenError::Clock(ClockError);
+        assert!(err.source().unwrap().is::<ClockError>());
+    }
+
+    #[test]
+    fn checked_generation_reports_timestamp_overflow() {
+        let mut gen = frozen_generator(1 << 48);
+        assert_eq!(gen.ulid_checked(), Err(GenError::TimestampOverflow));
+        assert_eq!(gen.next_monotonic_checked(), Err(GenError::TimestampOverflow));
+
+        let mut gen = frozen_generator((1 << 48) - 1);
+        assert_eq!(gen.ulid_checked().unwrap().timestamp_ms(), (1 << 48) - 1);
+    }
+
+    #[test]
+    fn checked_generation_reports_monotonic_overflow() {
+        let rng = RiggedRng(u64::MAX);
+        let mut gen = UlidGenerator::from_rng_and_clock(rng, FixedClock(1_469_922_850_259));
+        assert!(gen.next_monotonic_checked().is_ok());
+        assert_eq!(gen.next_monotonic_checked(), Err(GenError::MonotonicOverflow));
+        assert!(GenError::MonotonicOverflow.to_string().contains("same millisecond"));
+    }
+
+    #[test]
+    fn checked_generation_agrees_with_unchecked_generation() {
+        let mut gen = frozen_generator(1_469_922_850_259).with_submillis_ordering();
+        let mut twin = frozen_generator(1_469_922_850_259).with_submillis_ordering();
+        assert_eq!(gen.ulid_checked(), Ok(twin.ulid()));
+        assert_eq!(gen.next_monotonic_checked(), Ok(twin.next_monotonic().unwrap()));
+    }
+
+    #[test]
+    fn fallible_generation_agrees_with_infallible_generation() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let mut twin = frozen_generator(1_469_922_850_259);
+        assert_eq!(gen.try_ulid(), Ok(twin.ulid()));
+        assert!(UlidGenerator::try_new().is_ok());
+    }
+
+    #[test]
+    fn peeking_the_timestamp_doesnt_consume_randomness() {
+        let mut peeked = UlidGenerator::from_seed(42);
+        let mut untouched = UlidGenerator::from_seed(42);
+
+        let before = duration_since_epoch().as_millis() as u64;
+        let ts = peeked.peek_timestamp();
+        assert!(ts >= before);
+
+        let random_mask = (1 << 80) - 1;
+        for _ in 0..10 {
+            assert_eq!(peeked.ulid().bits & random_mask, untouched.ulid().bits & random_mask);
+        }
+    }
+
+    #[test]
+    fn cloned_generators_produce_the_same_randomness() {
+        let mut original = UlidGenerator::from_seed(12345);
+        let _ = original.ulid();
+        let mut forked = original.clone();
+
+        let random_mask = (1 << 80) - 1;
+        for _ in 0..10 {
+            assert_eq!(original.ulid().bits & random_mask, forked.ulid().bits & random_mask);
+        }
+    }
+
+    #[cfg(feature = "subtle")]
+    #[test]
+    fn constant_time_equality_agrees_with_eq() {
+        let mut gen = UlidGenerator::from_seed(419);
+        let ids: Vec<Ulid> = gen.by_ref().take(20).collect();
+
+        for a in &ids {
+            for b in &ids {
+                assert_eq!(a.ct_eq(b), a == b);
+            }
+            // differing only in the last bit
+            let neighbour = Ulid { bits: a.bits ^ 1 };
+            assert!(!a.ct_eq(&neighbour));
+        }
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroizing_wipes_the_rng_state() {
+        use zeroize::Zeroize;
+
+        let mut gen = UlidGenerator::from_seed(12345);
+        gen.zeroize();
+
+        // An all-zero xoroshiro128+ state only ever produces zeros
+        assert_eq!(gen.rand_bits(), 0);
+    }
+
+    #[test]
+    fn string_comparison_agrees_with_ulid_comparison() {
+        use itertools::Itertools;
+
+        let mut gen = UlidGenerator::new();
+        let mut ids: Vec<_> = (0..20).map(|i| gen.ulid_at(i * 7_919_000_003)).collect();
+        ids.extend(gen.take(20));
+
+        for pair in ids.into_iter().permutations(2) {
+            let (a, b) = (pair[0].to_string(), pair[1].to_string());
+            assert_eq!(str_cmp(&a, &b), Ok(pair[0].cmp(&pair[1])));
+        }
+    }
+
+    #[test]
+    fn string_comparison_requires_full_length_ulids() {
+        assert_eq!(
+            str_cmp("01ARZ3NDEKTSV4RRFFQ69G5FA", "01ARZ3NDEKTSV4RRFFQ69G5FAV"),
+            Err(ParseError::InvalidLength { len: 25 })
+        );
+        assert_eq!(
+            str_cmp("01ARZ3NDEKTSV4RRFFQ69G5FAV", ""),
+            Err(ParseError::InvalidLength { len: 0 })
+        );
+    }
+
+    #[test]
+    fn string_comparison_rejects_non_canonical_ulids() {
+        assert_eq!(
+            str_cmp("01arz3ndektsv4rrffq69g5fav", "01ARZ3NDEKTSV4RRFFQ69G5FAV"),
+            Err(ParseError::InvalidChar { position: 2, byte: b'a' })
+        );
+        assert_eq!(
+            str_cmp("01ARZ3NDEKTSV4RRFFQ69G5FAV", "01ARZ3NDEKTSV4RRFFQ69G5FAU"),
+            Err(ParseError::InvalidChar { position: 25, byte: b'U' })
+        );
+        assert_eq!(
+            str_cmp("81ARZ3NDEKTSV4RRFFQ69G5FAV", "01ARZ3NDEKTSV4RRFFQ69G5FAV"),
+            Err(ParseError::Overflow)
+        );
+    }
+
+    #[test]
+    fn injected_rng_and_clock_make_ulids_reproducible() {
+        use std::cell::Cell;
+
+        struct SteppingClock(Cell<u64>);
+
+        impl Clock for SteppingClock {
+            fn now_ms(&self) -> u64 {
+                let now = self.0.get();
+                self.0.set(now + 1);
+                now
+            }
+        }
+
+        let rng: Xoroshiro128 = SeedableRng::from_seed(&[0x9e37_79b9_7f4a_7c15, 0xbf58_476d_1ce4_e5b9][..]);
+        let clock = SteppingClock(Cell::new(1_469_922_850_259));
+        let mut gen = UlidGenerator::from_rng_and_clock(rng, clock);
+
+        assert_eq!(gen.ulid().to_string(), "01ARZ3NDEKC771XZPR8MR6WAER");
+        assert_eq!(gen.ulid().to_string(), "01ARZ3NDEMW3D3NRJQ1RWS1NZH");
+    }
+
+    #[test]
+    fn monotonic_ulids_increase_within_a_millisecond() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let first = gen.next_monotonic().unwrap();
+        let second = gen.next_monotonic().unwrap();
+
+        assert_eq!(first.timestamp_ms(), second.timestamp_ms());
+        assert_eq!(second.bits, first.bits + 1);
+    }
+
+    #[test]
+    fn monotonic_ulids_survive_the_clock_going_backwards() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let first = gen.next_monotonic().unwrap();
+        gen.clock = FixedClock(1_469_922_850_000);
+        let second = gen.next_monotonic().unwrap();
+
+        assert!(first < second);
+        assert_eq!(second.timestamp_ms(), 1_469_922_850_259);
+    }
+
+    #[test]
+    fn ulids_increase_while_the_clock_stands_still() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let ids: Vec<Ulid> = gen.by_ref().take(1_000).collect();
+
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|id| id.timestamp_ms() == 1_469_922_850_259));
+        assert_eq!(gen.stats().monotonic_increments, 999);
+    }
+
+    #[test]
+    fn ulids_draw_new_random_bits_once_the_clock_moves() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let first = gen.ulid();
+        assert_eq!(gen.ulid(), Ulid { bits: first.bits + 1 });
+
+        gen.clock = FixedClock(1_469_922_850_260);
+        let later = gen.ulid();
+        assert_ne!(later.random(), first.random() + 2);
+
+        // going backwards also restarts from a random point
+        gen.clock = FixedClock(1_469_922_850_000);
+        assert_eq!(gen.ulid().timestamp_ms(), 1_469_922_850_000);
+    }
+
+    #[test]
+    fn ulids_draw_new_random_bits_rather_than_overflow() {
+        let mut gen = UlidGenerator::from_rng_and_clock(RiggedRng(u64::MAX), FixedClock(1_469_922_850_259));
+        let first = gen.ulid();
+        assert_eq!(first.random(), RANDOM_MASK);
+        assert_eq!(gen.ulid(), first);
+    }
+
+    #[test]
+    fn bounded_iterators_yield_exactly_n_ulids() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let mut bounded = gen.bounded(10);
+        assert_eq!(bounded.len(), 10);
+
+        let ids: Vec<Ulid> = bounded.by_ref().collect();
+        assert_eq!(ids.len(), 10);
+        assert_eq!(bounded.remaining(), 0);
+        assert_eq!(bounded.next(), None);
+        assert_eq!(bounded.next(), None);
+
+        assert_eq!(gen.generated_count(), 10);
+        assert!(gen.bounded(0).next().is_none());
+    }
+
+    #[test]
+    fn bounded_iterators_can_be_stored_in_struct_fields() {
+        struct Batch<'a> {
+            ids: Bounded<'a, Xoroshiro128, FixedClock>,
+        }
+
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let batch = Batch { ids: gen.bounded(2) };
+        assert_eq!(batch.ids.count(), 2);
+    }
+
+    #[test]
+    fn fixed_timestamp_generators_share_the_timestamp() {
+        let ms = 1_469_922_850_259;
+        let mut gen = UlidGenerator::new_with_fixed_timestamp(ms);
+        let ids: Vec<Ulid> = gen.by_ref().take(10).collect();
+
+        assert!(ids.iter().all(|id| id.timestamp_ms() == ms));
+        assert_eq!(ids.iter().collect::<std::collections::HashSet<_>>().len(), ids.len());
+        assert_eq!(gen.next_monotonic().unwrap().timestamp_ms(), ms);
+        assert_eq!(gen.peek_timestamp(), ms);
+    }
+
+    #[test]
+    fn reserved_sentinels_are_skipped() {
+        let rigged = |values: Vec<u64>| {
+            let mut values = values.into_iter();
+            FnRng(move || values.next().unwrap_or(0x1234))
+        };
+
+        let mut gen = UlidGenerator::from_rng_and_clock(rigged(vec![0, 0]), FixedClock(0)).with_reserved_sentinels();
+        let id = gen.ulid();
+        assert_ne!(id, Ulid::new_nil());
+        assert_eq!(id.random(), 0x1234_0000_0000_0000_1234);
+
+        let max_ms = (1 << 48) - 1;
+        let mut gen = UlidGenerator::from_rng_and_clock(rigged(vec![u64::MAX, u64::MAX]), FixedClock(max_ms))
+            .with_reserved_sentinels();
+        assert_ne!(gen.ulid_at(max_ms), Ulid::from_u64s(u64::MAX, u64::MAX));
+
+        // without the option, the sentinels come out as they are
+        let mut gen = UlidGenerator::from_rng_and_clock(rigged(vec![0, 0]), FixedClock(0));
+        assert_eq!(gen.ulid(), Ulid::new_nil());
+    }
+
+    #[test]
+    fn reserved_sentinels_stop_increments_before_the_largest_ulid() {
+        let max = Ulid::from_u64s(u64::MAX, u64::MAX);
+        let mut gen = frozen_generator((1 << 48) - 1).with_reserved_sentinels();
+        gen.previous = Some(Ulid { bits: max.bits - 1 });
+        assert_eq!(gen.next_monotonic(), Err(MonotonicOverflow));
+
+        gen.last_stamped = Some(((gen.time_bits(), 0), Ulid { bits: max.bits - 1 }));
+        assert_ne!(gen.ulid(), max);
+    }
+
+    #[test]
+    fn duplicates_from_a_repeating_rng_are_detected() {
+        let rigged = UlidGenerator::from_rng_and_clock(RiggedRng(0x5eed), FixedClock(1_469_922_850_259));
+
+        let mut gen = rigged.with_duplicate_detection(16);
+        let _ = gen.ulid_at(1_000);
+        assert!(!gen.saw_duplicate());
+        let _ = gen.ulid_at(1_000);
+        assert!(gen.saw_duplicate());
+
+        // without detection, nothing is flagged
+        let mut gen = UlidGenerator::from_rng_and_clock(RiggedRng(0x5eed), FixedClock(0));
+        let _ = gen.ulid_at(1_000);
+        let _ = gen.ulid_at(1_000);
+        assert!(!gen.saw_duplicate());
+    }
+
+    #[test]
+    fn duplicate_detection_only_remembers_its_window() {
+        let mut gen = frozen_generator(1_469_922_850_259).with_duplicate_detection(3);
+        let ids: Vec<Ulid> = gen.by_ref().take(5).collect();
+        assert!(!gen.saw_duplicate());
+
+        // the oldest two have left the window
+        gen.last_stamped = Some(((gen.time_bits(), 0), Ulid { bits: ids[0].bits - 1 }));
+        let _ = gen.ulid();
+        assert!(!gen.saw_duplicate());
+
+        gen.last_stamped = Some(((gen.time_bits(), 0), Ulid { bits: ids[3].bits - 1 }));
+        let _ = gen.ulid();
+        assert!(gen.saw_duplicate());
+    }
+
+    #[test]
+    fn duplicate_detection_covers_monotonic_and_batch_generation() {
+        let mut gen = frozen_generator(1_469_922_850_259).with_duplicate_detection(100);
+        let batch = gen.ulid_batch_sorted(10);
+        assert!(!gen.saw_duplicate());
+
+        gen.previous = Some(Ulid { bits: batch[4].bits - 1 });
+        let _ = gen.next_monotonic().unwrap();
+        assert!(gen.saw_duplicate());
+    }
+
+    #[test]
+    fn supplied_random_components_are_masked_to_80_bits() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let before = gen.clone().rand_bits();
+
+        let id = gen.ulid_with_random(u128::MAX);
+        assert_eq!(id.timestamp_ms(), 1_469_922_850_259);
+        assert_eq!(id.random(), RANDOM_MASK);
+        assert_eq!(gen.ulid_with_random(0x1234).random(), 0x1234);
+
+        // the PRNG wasn't used
+        assert_eq!(gen.rand_bits(), before);
+    }
+
+    #[test]
+    fn supplied_random_components_get_the_current_time() {
+        let before = duration_since_epoch().as_millis() as u64;
+        let id = UlidGenerator::new().ulid_with_random(42);
+        let after = duration_since_epoch().as_millis() as u64;
+
+        assert!((before..=after).contains(&id.timestamp_ms()));
+        assert_eq!(id.random(), 42);
+    }
+
+    #[test]
+    fn merging_generators_keeps_the_output_sorted() {
+        let shard = |seed, start_ms, step_ms| {
+            UlidGenerator::from_rng_and_clock(DefaultRng::from_seed(seed), SteppingClock::new(start_ms, step_ms))
+        };
+        let ids: Vec<Ulid> = merge_sorted(vec![shard(1, 0, 2), shard(2, 1, 3)]).take(1_000).collect();
+
+        assert_eq!(ids.len(), 1_000);
+        assert!(ids.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // both shards contributed
+        assert!(ids.iter().any(|id| id.timestamp_ms() % 2 == 0));
+        assert!(ids.iter().any(|id| id.timestamp_ms() % 2 == 1));
+    }
+
+    #[test]
+    fn merging_finite_sources_yields_everything() {
+        let a: Vec<Ulid> = (0..5).map(|n| Ulid::from_timestamp_and_counter(n * 2, 0)).collect();
+        let b: Vec<Ulid> = (0..3).map(|n| Ulid::from_timestamp_and_counter(n * 2 + 1, 0)).collect();
+
+        let merged = merge_sorted(vec![a.clone(), b.clone(), Vec::new()]);
+        assert_eq!(merged.size_hint(), (8, Some(8)));
+
+        let mut expected = [a, b].concat();
+        expected.sort();
+        assert_eq!(merged.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn a_clock_that_always_moves_on_reproduces_seeded_sequences_from_0_6() {
+        let rng = DefaultRng::from_seed(12345);
+        let gen = UlidGenerator::from_rng_and_clock(rng, SteppingClock::new(1_469_922_850_259, 1));
+        let tails: Vec<String> = gen.take(5).map(|id| id.to_string()[20..].to_string()).collect();
+
+        // the values that 0.6.x gave for from_seed(12345)
+        assert_eq!(tails[0], "RBPBCT");
+        assert_eq!(tails[4], "BZBF00");
+    }
+
+    #[test]
+    fn sorted_batches_are_strictly_increasing() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let ids = gen.ulid_batch_sorted(100_000);
+
+        assert_eq!(ids.len(), 100_000);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|id| id.timestamp_ms() == 1_469_922_850_259));
+        assert_eq!(gen.generated_count(), 100_000);
+        assert!(gen.ulid_batch_sorted(0).is_empty());
+    }
+
+    #[test]
+    fn batches_over_a_range_are_sorted_and_stay_within_it() {
+        let mut gen = UlidGenerator::from_seed(42);
+        let ids = gen.batch_over_range(1_469_922_850_259, 1_469_922_860_259, 1_000);
+
+        assert_eq!(ids.len(), 1_000);
+        assert!(ids.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(ids
+            .iter()
+            .all(|id| (1_469_922_850_259..=1_469_922_860_259).contains(&id.timestamp_ms())));
+        assert_eq!(ids[0].timestamp_ms(), 1_469_922_850_259);
+        assert_eq!(ids[999].timestamp_ms(), 1_469_922_860_259);
+
+        let single = gen.batch_over_range(5, 5, 1);
+        assert_eq!(single[0].timestamp_ms(), 5);
+        assert!(gen.batch_over_range(0, 10, 0).is_empty());
+    }
+
+    #[test]
+    fn stats_count_the_clock_going_backwards() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let _ = gen.ulid();
+        gen.clock = FixedClock(1_469_922_850_000);
+        let _ = gen.ulid();
+        let _ = gen.ulid();
+
+        let stats = gen.stats();
+        assert_eq!(stats.generated, 3);
+        assert_eq!(stats.clock_backwards, 1);
+        assert_eq!(stats.monotonic_increments, 1);
+    }
+
+    #[test]
+    fn stats_count_monotonic_increments_and_the_clock_going_backwards() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let _ = gen.next_monotonic().unwrap();
+        let _ = gen.next_monotonic().unwrap();
+        gen.clock = FixedClock(1_469_922_850_000);
+        let _ = gen.next_monotonic().unwrap();
+        gen.clock = FixedClock(1_469_922_850_260);
+        let _ = gen.next_monotonic().unwrap();
+
+        assert_eq!(
+            gen.stats(),
+            GenStats { generated: 4, monotonic_increments: 2, clock_backwards: 1 }
+        );
+    }
+
+    #[test]
+    fn clock_skew_is_flagged_until_the_clock_catches_up() {
+        let ts = 1_469_922_850_259;
+        let mut gen = frozen_generator(ts);
+        let _ = gen.next_monotonic().unwrap();
+        assert!(!gen.clock_skew_detected());
+
+        gen.clock = FixedClock(ts - 100);
+        let _ = gen.next_monotonic().unwrap();
+        assert!(gen.clock_skew_detected());
+        let _ = gen.next_monotonic().unwrap();
+        assert!(gen.clock_skew_detected());
+
+        // back to the same millisecond as the last ULID isn't progress
+        gen.clock = FixedClock(ts);
+        let _ = gen.next_monotonic().unwrap();
+        assert!(gen.clock_skew_detected());
+
+        gen.clock = FixedClock(ts + 1);
+        let _ = gen.next_monotonic().unwrap();
+        assert!(!gen.clock_skew_detected());
+
+        // without earlier skew, an unchanged clock doesn't set the flag
+        let _ = gen.next_monotonic().unwrap();
+        assert!(!gen.clock_skew_detected());
+
+        gen.clock = FixedClock(ts);
+        let _ = gen.next_monotonic().unwrap();
+        assert!(gen.clock_skew_detected());
+        gen.clock = FixedClock(ts + 2);
+        let _ = gen.next_monotonic().unwrap();
+        assert!(!gen.clock_skew_detected());
+    }
+
+    #[test]
+    fn explicit_timestamps_are_not_clock_readings() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let _ = gen.ulid();
+        let _ = gen.ulid_at(0);
+        let _ = gen.ulid();
+
+        assert_eq!(gen.stats().clock_backwards, 0);
+        assert_eq!(gen.stats().generated, 3);
+    }
+
+    #[test]
+    fn resumed_monotonic_sequences_continue_above_the_last_ulid() {
+        let last: Ulid = CANONICAL.parse().unwrap();
+
+        let mut same_ms = frozen_generator(last.timestamp_ms());
+        same_ms.resume_monotonic(last);
+        assert_eq!(same_ms.next_monotonic().unwrap(), Ulid { bits: last.bits + 1 });
+
+        let mut behind = frozen_generator(last.timestamp_ms() - 1_000);
+        behind.resume_monotonic(last);
+        assert!(behind.next_monotonic().unwrap() > last);
+
+        let mut ahead = frozen_generator(last.timestamp_ms() + 1);
+        ahead.resume_monotonic(last);
+        assert!(ahead.next_monotonic().unwrap() > last);
+    }
+
+    #[test]
+    fn collision_probabilities_follow_the_birthday_bound() {
+        assert_eq!(Ulid::collision_probability(0), 0.0);
+        assert_eq!(Ulid::collision_probability(1), 0.0);
+
+        // a single pair collides with probability 2^-80
+        let pair = Ulid::collision_probability(2);
+        assert!((pair * 2f64.powi(80) - 1.0).abs() < 1e-9);
+
+        // about 2^40 values give even odds, as 1.1774 * sqrt(N) does
+        let half = Ulid::collision_probability((1.1774 * 2f64.powi(40)) as u64);
+        assert!((half - 0.5).abs() < 1e-4);
+
+        let likely = Ulid::collision_probability(1 << 41);
+        assert!((likely - (1.0 - (-2.0f64).exp())).abs() < 1e-9);
+
+        assert!(Ulid::collision_probability(u64::MAX) > 0.999_999);
+    }
+
+    #[test]
+    fn remaining_capacity_shrinks_as_monotonic_ulids_are_drawn() {
+        assert_eq!(Ulid::max_per_millisecond(), 1 << 80);
+
+        let mut gen = frozen_generator(1_469_922_850_259);
+        assert_eq!(gen.remaining_this_ms(), Ulid::max_per_millisecond());
+
+        let first = gen.next_monotonic().unwrap();
+        let remaining = gen.remaining_this_ms();
+        assert_eq!(remaining, RANDOM_MASK - first.random());
+
+        for drawn in 1..=10 {
+            let _ = gen.next_monotonic().unwrap();
+            assert_eq!(gen.remaining_this_ms(), remaining - drawn);
+        }
+    }
+
+    #[test]
+    fn remaining_capacity_is_zero_before_overflow() {
+        let rng = RiggedRng(u64::MAX);
+        let mut gen = UlidGenerator::from_rng_and_clock(rng, FixedClock(1_469_922_850_259));
+        let _ = gen.next_monotonic().unwrap();
+        assert_eq!(gen.remaining_this_ms(), 0);
+        assert_eq!(gen.next_monotonic(), Err(MonotonicOverflow));
+    }
 
-        let id = (*ctx).gen.ulid();
-        let slice = from_raw_parts_mut(dest as *mut u8, size);
-        base32::encode(id.bits, slice);
-        slice[ULID_LEN] = 0;
+    #[test]
+    fn monotonic_sequences_validate() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let ids: Vec<Ulid> = (0..100).map(|_| gen.next_monotonic().unwrap()).collect();
+        assert_eq!(Ulid::validate_monotonic_sequence(&ids), Ok(()));
+        assert_eq!(Ulid::validate_monotonic_sequence(&ids[..1]), Ok(()));
+        assert_eq!(Ulid::validate_monotonic_sequence(&[]), Ok(()));
+    }
 
-        ULID_LEN as c_int // cast is safe because ULID_LEN is tiny
+    #[test]
+    fn duplicates_break_monotonic_sequences() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let mut ids: Vec<Ulid> = (0..10).map(|_| gen.next_monotonic().unwrap()).collect();
+        ids.insert(5, ids[4]);
+        assert_eq!(Ulid::validate_monotonic_sequence(&ids), Err(5));
     }
 
-    /// Write the 128-bit ULID pointed by `id` to `dest` as a string.
-    ///
-    /// Crockford's Base32 alphabet is used, and exactly 27 bytes are written,
-    /// including the terminating null byte.
-    ///
-    /// The destination `dest` must be a valid, non-null, pointer to a `char`
-    /// buffer with `size` bytes, and should have at least 27 bytes.
-    ///
-    /// Returns the number of characters printed (excluding the terminating null
-    /// byte) on success, or a negative error code on failure.
-    #[no_mangle]
-    pub unsafe extern "C" fn ulid_write(id: &ulid, dest: *mut c_char, size: size_t) -> c_int {
-        if size < ULID_LEN + 1 {
-            return -ERANGE;
+    #[test]
+    fn out_of_order_ulids_break_monotonic_sequences() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let mut ids: Vec<Ulid> = (0..10).map(|_| gen.next_monotonic().unwrap()).collect();
+        ids.swap(0, 9);
+        assert_eq!(Ulid::validate_monotonic_sequence(&ids), Err(1));
+    }
+
+    #[test]
+    fn resuming_from_an_older_ulid_is_ignored() {
+        let mut gen = frozen_generator(1_469_922_850_259);
+        let newest = gen.next_monotonic().unwrap();
+        gen.resume_monotonic(Ulid::new_nil());
+        assert!(gen.next_monotonic().unwrap() > newest);
+    }
+
+    #[test]
+    fn monotonic_generation_reports_random_overflow() {
+        let ts = 1_469_922_850_259;
+        let mut gen = frozen_generator(ts);
+        gen.previous = Some(Ulid { bits: ((ts as u128) << 80) | (RANDOM_MASK - 2) });
+
+        assert_eq!(gen.next_monotonic().unwrap().bits & RANDOM_MASK, RANDOM_MASK - 1);
+        assert_eq!(gen.next_monotonic().unwrap().bits & RANDOM_MASK, RANDOM_MASK);
+        assert_eq!(gen.next_monotonic(), Err(MonotonicOverflow));
+
+        // the next millisecond starts afresh
+        gen.clock = FixedClock(ts + 1);
+        assert_eq!(gen.next_monotonic().unwrap().timestamp_ms(), ts + 1);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn monotonic_streams_yield_increasing_ulids() {
+        use futures::StreamExt;
+
+        let gen = UlidGenerator::deterministic();
+        let ids: Vec<Ulid> = futures::executor::block_on(gen.into_monotonic_stream().take(100).collect());
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn monotonic_streams_wait_for_the_clock_instead_of_blocking() {
+        use core::pin::Pin;
+        use core::task::{Context, Poll};
+        use futures::task::{waker_ref, ArcWake};
+        use futures::Stream;
+        use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::SeqCst};
+        use std::sync::Arc;
+        use std::time::Instant;
+
+        #[derive(Default)]
+        struct WakeRecorder {
+            count: AtomicUsize,
+            woken_ms: AtomicU64,
         }
 
-        let slice = from_raw_parts_mut(dest as *mut u8, size);
-        base32::encode(std::mem::transmute(*id), slice);
-        slice[ULID_LEN] = 0;
+        impl ArcWake for WakeRecorder {
+            fn wake_by_ref(arc_self: &Arc<Self>) {
+                arc_self.woken_ms.store(UlidGenerator::new().time_bits() as u64, SeqCst);
+                arc_self.count.fetch_add(1, SeqCst);
+            }
+        }
 
-        ULID_LEN as c_int // cast is safe because ULID_LEN is tiny
+        let ts = 1_469_922_850_259;
+        let mut gen = frozen_generator(ts);
+        let exhausted = Ulid { bits: ((ts as u128) << 80) | RANDOM_MASK };
+        gen.previous = Some(exhausted);
+        let mut stream = gen.into_monotonic_stream();
+
+        let wakes = Arc::new(WakeRecorder::default());
+        let waker = waker_ref(&wakes);
+        let mut cx = Context::from_waker(&waker);
+
+        // the millisecond is used up, so the stream waits rather than blocking
+        let polled_ms = UlidGenerator::new().time_bits() as u64;
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(wakes.count.load(SeqCst), 0, "woken before the clock moved");
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while wakes.count.load(SeqCst) == 0 {
+            assert!(Instant::now() < deadline, "never woken");
+            std::thread::sleep(Duration::from_micros(100));
+        }
+        assert!(wakes.woken_ms.load(SeqCst) > polled_ms);
+
+        stream.ulid_gen.clock = FixedClock(ts + 1);
+        match Pin::new(&mut stream).poll_next(&mut cx) {
+            Poll::Ready(Some(id)) => {
+                assert!(id > exhausted);
+                assert_eq!(id.timestamp_ms(), ts + 1);
+            }
+            other => panic!("expected a ULID, got {:?}", other),
+        }
+
+        // one timer for both polls, so one wake
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(wakes.count.load(SeqCst), 1);
     }
-}
 
-#[cfg(test)]
-mod that {
-    use super::*;
+    #[test]
+    fn blocking_monotonic_generation_waits_out_overflow() {
+        let mut gen = UlidGenerator::new();
+        let exhausted = Ulid { bits: ((gen.time_bits() + 1) << 80) | RANDOM_MASK };
+        gen.previous = Some(exhausted);
+
+        let id = gen.next_monotonic_blocking(Duration::from_secs(10)).unwrap();
+        assert!(id > exhausted);
+        assert!(id.timestamp_ms() > exhausted.timestamp_ms());
+    }
 
     #[test]
-    #[cfg_attr(miri, ignore)] // too slow and unlikely to pass in Miri
-    fn each_ulid_is_unique() {
-        use itertools::Itertools;
+    fn blocking_monotonic_generation_reports_a_stuck_clock() {
+        let ts = 1_469_922_850_259;
+        let mut gen = UlidGenerator::new_with_fixed_timestamp(ts);
+        let exhausted = Ulid::max_for_timestamp(ts);
+        gen.previous = Some(exhausted);
 
-        let ulids: Vec<_> = (0..100).map(|_| ulid()).collect();
-        println!("{:?}", ulids);
-        for pair in ulids.into_iter().permutations(2) {
-            assert_ne!(pair[0], pair[1]);
+        assert_eq!(gen.next_monotonic_blocking(Duration::from_millis(10)), Err(MonotonicOverflow));
+
+        // the monotonic state survives, so nothing out of order comes next
+        assert_eq!(gen.previous, Some(exhausted));
+        gen.clock = FixedClock(ts + 1);
+        let id = gen.next_monotonic_blocking(Duration::from_millis(10)).unwrap();
+        assert!(id > exhausted);
+    }
+
+    #[test]
+    fn string_successors_are_one_higher() {
+        assert_eq!(successor_str(CANONICAL).unwrap(), "01ARZ3NDEKTSV4RRFFQ69G5FAW");
+        assert_eq!(successor_str("01ARZ3NDEKTSV4RRFFQ69G5FAZ").unwrap(), "01ARZ3NDEKTSV4RRFFQ69G5FB0");
+        assert_eq!(successor_str("01arz3ndektsv4rrffq69g5fav").unwrap(), "01ARZ3NDEKTSV4RRFFQ69G5FAW");
+    }
+
+    #[test]
+    fn string_successors_report_overflow() {
+        let max = Ulid { bits: u128::MAX }.to_string();
+        assert_eq!(max, "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+        assert_eq!(successor_str(&max), Err(ParseError::Overflow));
+        assert_eq!(successor_str("ZZZZZZZZZZZZZZZZZZZZZZZZZZ"), Err(ParseError::Overflow));
+        assert_eq!(successor_str("not a ulid"), Err(ParseError::InvalidLength { len: 10 }));
+    }
+
+    #[test]
+    fn bulk_ulids_share_a_timestamp_and_increase() {
+        let ms = 1_469_922_850_259;
+        let ids = UlidGenerator::new().ulids_for_ms(ms, 1000);
+
+        assert_eq!(ids.len(), 1000);
+        assert!(ids.iter().all(|id| id.timestamp_ms() == ms));
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn bulk_ulids_dont_overflow_the_random_component() {
+        let mut gen = UlidGenerator::from_rng_and_clock(RiggedRng(u64::MAX), FixedClock(0));
+        let ids = gen.ulids_for_ms(1_469_922_850_259, 10);
+
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|id| id.timestamp_ms() == 1_469_922_850_259));
+        assert_eq!(ids[9].random(), RANDOM_MASK);
+        assert!(UlidGenerator::new().ulids_for_ms(0, 0).is_empty());
+    }
+
+    #[test]
+    fn timestamp_bounds_enclose_every_ulid_with_that_timestamp() {
+        let ms = 1_469_922_850_259;
+        for id in UlidGenerator::new().ulids_for_ms(ms, 100) {
+            assert!(Ulid::min_for_timestamp(ms) <= id && id <= Ulid::max_for_timestamp(ms));
         }
+        assert!(Ulid::max_for_timestamp(ms) < Ulid::min_for_timestamp(ms + 1));
     }
 
     #[test]
-    fn newer_ulids_are_higher() {
-        use std::thread::sleep;
-        use std::time::Duration;
+    fn sets_iterate_in_insertion_time_order() {
+        let mut gen = UlidGenerator::new();
+        let mut set = UlidSet::new();
+        let newest = gen.ulid_at(3_000);
+        let oldest = gen.ulid_at(1_000);
+        set.extend(vec![newest, oldest]);
+        let middle = set.insert_new(&mut frozen_generator(2_000));
 
-        let a = ulid();
-        sleep(Duration::from_millis(2));
-        let b = ulid();
-        assert!(a < b);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&middle));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![oldest, middle, newest]);
+        assert_eq!(set.first(), Some(&oldest));
+        assert_eq!(set.last(), Some(&newest));
+    }
+
+    #[test]
+    fn sets_can_be_queried_by_time_range() {
+        let mut gen = UlidGenerator::new();
+        let set: UlidSet = (0..10).map(|i| gen.ulid_at(1_000 * i)).collect();
+
+        let selected: Vec<_> = set.range_ms(2_000, 4_000).map(|id| id.timestamp_ms()).collect();
+        assert_eq!(selected, vec![2_000, 3_000, 4_000]);
+        assert_eq!(set.range_ms(2_001, 2_999).count(), 0);
+        assert_eq!(set.range_ms(4_000, 2_000).count(), 0);
+    }
+
+    #[test]
+    fn time_indexes_can_be_queried_by_time_range() {
+        let mut gen = UlidGenerator::new();
+        let mut index = UlidTimeIndex::new();
+        for ms in 0..10 {
+            // several entries per millisecond, including at both extremes
+            index.insert(Ulid::min_for_timestamp(1_000 * ms), (ms, "min"));
+            index.insert(gen.ulid_at(1_000 * ms), (ms, "random"));
+            index.insert(Ulid::max_for_timestamp(1_000 * ms), (ms, "max"));
+        }
+        assert_eq!(index.len(), 30);
+
+        let selected: Vec<_> = index.range_time(2_000, 4_000).map(|(_, &(ms, _))| ms).collect();
+        assert_eq!(selected, vec![2, 2, 2, 3, 3, 3, 4, 4, 4]);
+        assert_eq!(index.range_time(2_001, 2_999).count(), 0);
+        assert_eq!(index.range_time(4_000, 2_000).count(), 0);
+        assert_eq!(index.range_time(9_000, 9_000).count(), 3);
+
+        let since: Vec<_> = index.since(8_000).map(|(_, &v)| v).collect();
+        assert_eq!(since, vec![(8, "min"), (8, "random"), (8, "max"), (9, "min"), (9, "random"), (9, "max")]);
+        assert_eq!(index.since(9_001).count(), 0);
+        assert_eq!(index.since(0).count(), 30);
+    }
+
+    #[test]
+    fn time_indexes_behave_like_maps() {
+        let id: Ulid = CANONICAL.parse().unwrap();
+        let mut index: UlidTimeIndex<i32> = vec![(id, 1)].into_iter().collect();
+        assert_eq!(index.insert(id, 2), Some(1));
+        assert_eq!(index.get(&id), Some(&2));
+        assert_eq!((&index).into_iter().count(), 1);
+        assert_eq!(index.remove(&id), Some(2));
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn node_ids_separate_otherwise_identical_generators() {
+        let mut a = frozen_generator(1_469_922_850_259).with_node_id(1);
+        let mut b = frozen_generator(1_469_922_850_259).with_node_id(2);
+
+        for _ in 0..10 {
+            let (id_a, id_b) = (a.ulid(), b.ulid());
+            assert_ne!(id_a, id_b);
+            assert_eq!(id_a.random() & u64::MAX as u128, id_b.random() & u64::MAX as u128);
+            assert_eq!(id_a.random() >> 64, 1);
+            assert_eq!(id_b.random() >> 64, 2);
+        }
+    }
+
+    #[test]
+    fn monotonic_generation_never_changes_the_node_id() {
+        let ts = 1_469_922_850_259;
+        let mut gen = frozen_generator(ts).with_node_id(0xabcd);
+        gen.previous = Some(Ulid { bits: ((ts as u128) << 80) | (0xabcd << 64) | (u64::MAX as u128 - 1) });
+
+        assert_eq!(gen.next_monotonic().unwrap().random() >> 64, 0xabcd);
+        assert_eq!(gen.next_monotonic(), Err(MonotonicOverflow));
+        assert!(gen.ulids_for_ms(ts, 10).iter().all(|id| id.random() >> 64 == 0xabcd));
+    }
+
+    #[test]
+    fn normalizing_maps_confusable_letters_onto_digits() {
+        let cases = [("O", "0"), ("o", "0"), ("I", "1"), ("i", "1"), ("L", "1"), ("l", "1")];
+
+        for &(input, expected) in &cases {
+            assert_eq!(normalize_crockford(input), expected, "normalizing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn normalizing_uppercases_the_alphabet() {
+        assert_eq!(
+            normalize_crockford("0123456789abcdefghjkmnpqrstvwxyz"),
+            "0123456789ABCDEFGHJKMNPQRSTVWXYZ"
+        );
+    }
+
+    #[test]
+    fn normalizing_leaves_canonical_ulids_untouched() {
+        for _ in 0..100 {
+            let id = ulid();
+            assert_eq!(normalize_crockford(&id), id);
+        }
+    }
+
+    #[test]
+    fn normalizing_passes_through_other_characters() {
+        assert_eq!(normalize_crockford("u-*é"), "U-*é");
+    }
+
+    #[cfg(feature = "serde")]
+    mod serialization {
+        use std::collections::HashMap;
+
+        use serde::{Deserialize, Serialize};
+
+        use crate::*;
+
+        #[test]
+        fn ulids_serialize_as_strings() {
+            let id: Ulid = super::CANONICAL.parse().unwrap();
+            let json = serde_json::to_string(&id).unwrap();
+            assert_eq!(json, "\"01ARZ3NDEKTSV4RRFFQ69G5FAV\"");
+            assert_eq!(serde_json::from_str::<Ulid>(&json).unwrap(), id);
+        }
+
+        #[test]
+        fn options_and_vecs_of_ulids_roundtrip() {
+            let ids: Vec<Ulid> = UlidGenerator::new().take(3).collect();
+            let json = serde_json::to_string(&ids).unwrap();
+            assert_eq!(serde_json::from_str::<Vec<Ulid>>(&json).unwrap(), ids);
+
+            let maybe = Some(Ulid::new());
+            let json = serde_json::to_string(&maybe).unwrap();
+            assert_eq!(serde_json::from_str::<Option<Ulid>>(&json).unwrap(), maybe);
+        }
+
+        #[test]
+        fn components_roundtrip_through_json() {
+            let id: Ulid = super::CANONICAL.parse().unwrap();
+            let json = serde_json::to_string(&id.components()).unwrap();
+            assert_eq!(json, r#"{"timestamp_ms":1469922850259,"random":"TSV4RRFFQ69G5FAV"}"#);
+
+            let parts: UlidComponents = serde_json::from_str(&json).unwrap();
+            assert_eq!(Ulid::from(parts), id);
+        }
+
+        #[test]
+        fn components_with_bad_random_fields_are_rejected() {
+            let json = r#"{"timestamp_ms":1469922850259,"random":"TSV4RRFFQ69G5FA"}"#;
+            assert!(serde_json::from_str::<UlidComponents>(json).is_err());
+
+            let json = r#"{"timestamp_ms":1469922850259,"random":"TSV4RRFFQ69G5FAU"}"#;
+            assert!(serde_json::from_str::<UlidComponents>(json).is_err());
+        }
+
+        #[test]
+        fn hashmaps_with_ulid_keys_roundtrip_through_json() {
+            #[derive(Serialize, Deserialize, PartialEq, Debug)]
+            struct Scores {
+                #[serde(with = "crate::serde_str_key")]
+                by_id: HashMap<Ulid, i32>,
+            }
+
+            let scores = Scores {
+                by_id: UlidGenerator::new().take(5).zip(0..).collect(),
+            };
+
+            let json = serde_json::to_string(&scores).unwrap();
+            let first = scores.by_id.keys().next().unwrap().to_string();
+            assert!(json.contains(&format!("\"{}\":", first)));
+            assert_eq!(serde_json::from_str::<Scores>(&json).unwrap(), scores);
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Config {
+            owner: Ulid,
+            members: Vec<Ulid>,
+            scores: HashMap<Ulid, i64>,
+        }
+
+        fn sample_config() -> Config {
+            let mut gen = UlidGenerator::new();
+            Config {
+                owner: gen.ulid(),
+                members: gen.by_ref().take(3).collect(),
+                scores: gen.take(3).zip(0..).collect(),
+            }
+        }
+
+        #[test]
+        fn ulid_values_and_keys_roundtrip_through_yaml() {
+            let config = sample_config();
+            let yaml = serde_yaml::to_string(&config).unwrap();
+            assert!(yaml.contains(&format!("owner: {}", config.owner)));
+            for id in config.scores.keys() {
+                assert!(yaml.contains(&format!("{}: ", id)));
+            }
+
+            assert_eq!(serde_yaml::from_str::<Config>(&yaml).unwrap(), config);
+        }
+
+        #[test]
+        fn ulid_values_and_keys_roundtrip_through_toml() {
+            let config = sample_config();
+            let text = toml::to_string(&config).unwrap();
+            assert!(text.contains(&format!("owner = \"{}\"", config.owner)));
+            for id in config.scores.keys() {
+                assert!(text.contains(&format!("{} = ", id)));
+            }
+
+            assert_eq!(toml::from_str::<Config>(&text).unwrap(), config);
+        }
+
+        #[test]
+        fn invalid_ulids_in_yaml_and_toml_are_rejected() {
+            assert!(serde_yaml::from_str::<Ulid>("01ARZ3NDEKTSV4RRFFQ69G5FAU").is_err());
+
+            #[derive(Deserialize, Debug)]
+            struct Keyed {
+                #[allow(dead_code)]
+                scores: HashMap<Ulid, i64>,
+            }
+            assert!(toml::from_str::<Keyed>("[scores]\nnot-a-ulid = 1\n").is_err());
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Binary {
+            #[serde(with = "crate::serde_binary")]
+            id: Ulid,
+        }
+
+        #[test]
+        fn binary_form_is_a_messagepack_bin_field() {
+            let id: Ulid = super::CANONICAL.parse().unwrap();
+            let encoded = rmp_serde::to_vec(&Binary { id }).unwrap();
+
+            // fixarray marker, bin8 marker, length byte, then the raw bytes
+            assert_eq!(encoded.len(), 1 + 2 + ULID_BINARY_LEN);
+            assert_eq!(&encoded[..3], &[0x91, 0xc4, ULID_BINARY_LEN as u8]);
+            assert_eq!(&encoded[3..], &id.to_be_bytes());
+
+            assert_eq!(rmp_serde::from_slice::<Binary>(&encoded).unwrap(), Binary { id });
+        }
+
+        #[cfg(feature = "cbor")]
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Tagged(#[serde(with = "crate::cbor_tagged")] Ulid);
+
+        #[cfg(feature = "cbor")]
+        #[test]
+        fn cbor_form_is_a_tagged_byte_string() {
+            let id: Ulid = super::CANONICAL.parse().unwrap();
+            let mut encoded = Vec::new();
+            ciborium::ser::into_writer(&Tagged(id), &mut encoded).unwrap();
+
+            // tag with a 4-byte number, then a 16-byte byte string
+            assert_eq!(&encoded[..5], &[0xda, 0x75, 0x6c, 0x69, 0x64]);
+            assert_eq!(encoded[5], 0x40 | ULID_BINARY_LEN as u8);
+            assert_eq!(&encoded[6..], &id.to_be_bytes());
+
+            let decoded: Tagged = ciborium::de::from_reader(&encoded[..]).unwrap();
+            assert_eq!(decoded, Tagged(id));
+        }
+
+        #[cfg(feature = "cbor")]
+        #[test]
+        fn cbor_form_requires_the_tag() {
+            let id: Ulid = super::CANONICAL.parse().unwrap();
+            let mut untagged = Vec::new();
+            ciborium::ser::into_writer(&Binary { id }, &mut untagged).unwrap();
+            assert!(ciborium::de::from_reader::<Tagged, _>(&untagged[..]).is_err());
+
+            let mut wrong_tag = vec![0xd8, 37, 0x50];
+            wrong_tag.extend_from_slice(&id.to_be_bytes());
+            assert!(ciborium::de::from_reader::<Tagged, _>(&wrong_tag[..]).is_err());
+        }
+
+        #[test]
+        fn binary_form_falls_back_to_sequences_for_json() {
+            let id: Ulid = super::CANONICAL.parse().unwrap();
+            let json = serde_json::to_string(&Binary { id }).unwrap();
+            assert_eq!(serde_json::from_str::<Binary>(&json).unwrap(), Binary { id });
+
+            assert!(serde_json::from_str::<Binary>(r#"{"id":[1,2,3]}"#).is_err());
+        }
+    }
+
+    mod typed {
+        use std::collections::HashSet;
+
+        use crate::typed::Id;
+        use crate::*;
+
+        // neither implements any traits, to show that Id<T> doesn't need them
+        struct User;
+        struct Order;
+
+        #[test]
+        fn typed_ids_roundtrip_through_strings() {
+            let id: Id<User> = Id::new();
+            let parsed: Id<User> = id.to_string().parse().unwrap();
+            assert_eq!(parsed, id);
+            assert_eq!(format!("{:?}", id), format!("Id({:?})", id.ulid()));
+        }
+
+        #[test]
+        fn typed_ids_order_and_hash_like_ulids() {
+            let mut ulids: Vec<Ulid> = UlidGenerator::new().take(10).collect();
+            let mut ids: Vec<Id<Order>> = ulids.iter().rev().map(|&u| Id::from(u)).collect();
+            ulids.sort();
+            ids.sort();
+            assert!(ids.iter().map(|id| id.ulid()).eq(ulids));
+
+            let set: HashSet<Id<Order>> = ids.iter().copied().chain(ids.iter().copied()).collect();
+            assert_eq!(set.len(), 10);
+        }
+
+        #[test]
+        fn typed_ids_convert_to_and_from_ulids() {
+            let ulid: Ulid = super::CANONICAL.parse().unwrap();
+            let user: Id<User> = ulid.into();
+            let order: Id<Order> = Id::from_ulid(Ulid::from(user));
+            assert_eq!(user.ulid(), order.ulid());
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn typed_ids_serialize_like_ulids() {
+            let id: Id<User> = super::CANONICAL.parse().unwrap();
+            let json = serde_json::to_string(&id).unwrap();
+            assert_eq!(json, serde_json::to_string(&id.ulid()).unwrap());
+            assert_eq!(serde_json::from_str::<Id<User>>(&json).unwrap(), id);
+        }
     }
 
-    #[cfg(ffi)]
+    #[cfg(feature = "ffi")]
     mod ffi {
         use std::{ffi::CStr, os::raw::c_char};
 
@@ -414,6 +7012,19 @@ mod that {
             assert!(as_u32 <= 20);
         }
 
+        #[test]
+        fn binary_form_round_trips_and_preserves_order() {
+            let a: Ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+            let b: Ulid = "01BX5ZZKBKACTAV9WEVGEMMVS0".parse().unwrap();
+            let c = Ulid::from_u64s(u64::MAX, u64::MAX);
+
+            let bin: Vec<ffi::ulid> = vec![a.into(), b.into(), c.into()];
+            assert!(bin.windows(2).all(|w| w[0] < w[1]));
+
+            let back: Vec<Ulid> = bin.into_iter().map(Ulid::from).collect();
+            assert_eq!(back, vec![a, b, c]);
+        }
+
         #[test]
         fn can_create_new_ulid() {
             let mut dest = [0u8; ULID_BINARY_LEN];
@@ -422,6 +7033,29 @@ mod that {
             assert_ne!(dest, [0u8; ULID_BINARY_LEN]); // should be impossible after 1-1-1970
         }
 
+        #[test]
+        fn can_create_new_ulid_at_timestamp() {
+            let mut dest = [0u8; ULID_BINARY_LEN];
+            let ctx = ffi::ulid_init(42);
+
+            unsafe { ffi::ulid_new_at(ctx, 1469922850259, &mut dest) };
+            assert_eq!(u128::from_be_bytes(dest) >> 80, 1469922850259);
+
+            unsafe { ffi::ulid_ctx_destroy(ctx) };
+        }
+
+        #[test]
+        fn seeded_ulids_at_a_timestamp_dont_depend_on_the_clock() {
+            let mut dest = [0u8; ULID_BINARY_LEN];
+            let ctx = ffi::ulid_init(42);
+
+            assert_eq!(unsafe { ffi::ulid_new_at(ctx, 1469922850259, &mut dest) }, 0);
+            let expected = UlidGenerator::from_seed(42).ulid_at(1469922850259);
+            assert_eq!(dest, ffi::ulid::from(expected));
+
+            unsafe { ffi::ulid_ctx_destroy(ctx) };
+        }
+
         #[test]
         fn can_create_new_ulid_as_base32() {
             let mut dest = [0_i8; ULID_LEN + 1];
@@ -446,6 +7080,32 @@ mod that {
             assert_eq!(dest[0], 0); // nothing written to dest
         }
 
+        struct BrokenClock;
+
+        impl Clock for BrokenClock {
+            fn now_ms(&self) -> u64 {
+                panic!("must not be called across the FFI boundary");
+            }
+
+            fn try_now_ms(&self) -> Result<u64, ClockError> {
+                Err(ClockError)
+            }
+        }
+
+        #[test]
+        fn clock_failures_become_error_codes() {
+            let rng: xorshift::Xoroshiro128 = xorshift::SeedableRng::from_seed(&[1, 2][..]);
+            let mut gen = UlidGenerator::from_rng_and_clock(rng, BrokenClock);
+
+            let mut id = [0u8; ULID_BINARY_LEN];
+            assert_eq!(ffi::new_into(&mut gen, &mut id), -ffi::ULID_ECLOCK);
+            assert_eq!(id, [0u8; ULID_BINARY_LEN]);
+
+            let mut dest = [0u8; ULID_LEN + 1];
+            assert_eq!(ffi::write_new_into(&mut gen, &mut dest), -ffi::ULID_ECLOCK);
+            assert_eq!(dest[0], 0); // nothing written to dest
+        }
+
         #[test]
         fn can_encode_binary_ulid_as_base32() {
             let mut id = [0u8; ULID_BINARY_LEN];
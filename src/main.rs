@@ -1,5 +1,111 @@
-use ulid::ulid;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::process::exit;
+
+use ulid::{ulid, Ulid};
+
+const USAGE: &str = "\
+Usage: ulid [--stdin] [--sort] [--dedup]
+
+With no options, generate a ULID and print it to stdout.
+
+Options:
+    --stdin    read ULIDs from stdin, one per line, and print them in
+               canonical form. Invalid lines are reported on stderr
+               and skipped, and the exit status is 1.
+    --sort     sort the ULIDs read from stdin (implies --stdin)
+    --dedup    remove repeated ULIDs read from stdin (implies --stdin)
+    --help     print this message";
+
+#[derive(Default)]
+struct Options {
+    stdin: bool,
+    sort: bool,
+    dedup: bool,
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options::default();
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--stdin" => opts.stdin = true,
+            "--sort" => opts.sort = true,
+            "--dedup" => opts.dedup = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            other => {
+                eprintln!("ulid: unknown option: {}\n\n{}", other, USAGE);
+                exit(2);
+            }
+        }
+    }
+
+    opts.stdin |= opts.sort || opts.dedup;
+    opts
+}
+
+/// Copy the ULIDs read from `input` to `out` in canonical form, reporting
+/// invalid lines on stderr and carrying on. Without `--sort`, each ULID is
+/// written as soon as its line is read. Returns whether every line was
+/// valid.
+fn filter(input: impl BufRead, mut out: impl Write, opts: &Options) -> io::Result<bool> {
+    let mut all_valid = true;
+    let mut seen = HashSet::new();
+    let mut sorted = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line
+            .map_err(|err| io::Error::new(err.kind(), format!("failed to read stdin: {}", err)))?;
+        let id = match Ulid::parse_trimmed(&line) {
+            Ok(id) => id,
+            Err(err) => {
+                eprintln!("ulid: line {}: {}: {:?}", i + 1, err, line);
+                all_valid = false;
+                continue;
+            }
+        };
+
+        if opts.sort {
+            sorted.push(id);
+        } else if !opts.dedup || seen.insert(id) {
+            writeln!(out, "{}", id)?;
+        }
+    }
+
+    if opts.sort {
+        sorted.sort_unstable();
+        if opts.dedup {
+            sorted.dedup();
+        }
+        for id in sorted {
+            writeln!(out, "{}", id)?;
+        }
+    }
+
+    Ok(all_valid)
+}
 
 fn main() {
-    println!("{}", ulid());
+    let opts = parse_args();
+
+    if !opts.stdin {
+        println!("{}", ulid());
+        return;
+    }
+
+    // stdout is line buffered, so without --sort each ULID goes out as
+    // soon as it has been read
+    let stdout = io::stdout();
+    match filter(io::stdin().lock(), stdout.lock(), &opts) {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => exit(1),
+        Err(err) => {
+            eprintln!("ulid: {}", err);
+            exit(1);
+        }
+    }
 }
@@ -0,0 +1,112 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ulid"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const UNSORTED: &str = "\
+01BX5ZZKBKACTAV9WEVGEMMVS0
+01ARZ3NDEKTSV4RRFFQ69G5FAV
+01bx5zzkbkactav9wevgemmvs0
+01ARYZ6S41TSV4RRFFQ69G5FAV
+";
+
+#[test]
+fn sorting_orders_ulids_and_canonicalizes_them() {
+    let output = run(&["--sort"], UNSORTED);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+01ARYZ6S41TSV4RRFFQ69G5FAV
+01ARZ3NDEKTSV4RRFFQ69G5FAV
+01BX5ZZKBKACTAV9WEVGEMMVS0
+01BX5ZZKBKACTAV9WEVGEMMVS0
+"
+    );
+}
+
+#[test]
+fn sorting_and_deduplicating_removes_repeats() {
+    let output = run(&["--sort", "--dedup"], UNSORTED);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+01ARYZ6S41TSV4RRFFQ69G5FAV
+01ARZ3NDEKTSV4RRFFQ69G5FAV
+01BX5ZZKBKACTAV9WEVGEMMVS0
+"
+    );
+}
+
+#[test]
+fn deduplicating_alone_keeps_the_input_order() {
+    let output = run(&["--dedup"], UNSORTED);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+01BX5ZZKBKACTAV9WEVGEMMVS0
+01ARZ3NDEKTSV4RRFFQ69G5FAV
+01ARYZ6S41TSV4RRFFQ69G5FAV
+"
+    );
+}
+
+#[test]
+fn invalid_lines_are_reported_with_their_line_number_and_skipped() {
+    let output = run(
+        &["--stdin"],
+        "01ARZ3NDEKTSV4RRFFQ69G5FAV\nnot a ulid\n01bx5zzkbkactav9wevgemmvs0\nalso bad\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "01ARZ3NDEKTSV4RRFFQ69G5FAV\n01BX5ZZKBKACTAV9WEVGEMMVS0\n"
+    );
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2"), "{}", stderr);
+    assert!(stderr.contains("line 4"), "{}", stderr);
+}
+
+#[test]
+fn ulids_are_written_as_they_are_read() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ulid"))
+        .arg("--stdin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // stdin is still open, so the line can only arrive if it was streamed
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"01arz3ndektsv4rrffq69g5fav\n").unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "01ARZ3NDEKTSV4RRFFQ69G5FAV\n");
+
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn no_arguments_generates_a_ulid() {
+    let output = run(&[], "");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.trim_end().parse::<ulid::Ulid>().is_ok());
+}